@@ -2,4 +2,6 @@
 
 pub mod prelude;
 
+pub mod action_journal;
+pub mod quarantine_entry;
 pub mod reference_image;
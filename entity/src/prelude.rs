@@ -1,3 +1,5 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.0
 
+pub use super::action_journal::Entity as ActionJournal;
+pub use super::quarantine_entry::Entity as QuarantineEntry;
 pub use super::reference_image::Entity as ReferenceImage;
@@ -13,6 +13,11 @@ pub struct Model {
     pub hash: Vec<u8>,
     pub created_at: DateTimeUtc,
     pub updated_at: DateTimeUtc,
+    pub hash_version: i32,
+    pub digest: Option<String>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub collection: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -1,12 +1,26 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20250310_123143_reference_images;
+mod m20261019_090000_reference_image_hash_version;
+mod m20261019_100000_reference_image_bundle_metadata;
+mod m20261019_110000_reference_image_unique_filepath;
+mod m20261019_120000_quarantine_entries;
+mod m20261019_130000_action_journal;
+mod m20261019_140000_action_journal_reverts_entry;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20250310_123143_reference_images::Migration)]
+        vec![
+            Box::new(m20250310_123143_reference_images::Migration),
+            Box::new(m20261019_090000_reference_image_hash_version::Migration),
+            Box::new(m20261019_100000_reference_image_bundle_metadata::Migration),
+            Box::new(m20261019_110000_reference_image_unique_filepath::Migration),
+            Box::new(m20261019_120000_quarantine_entries::Migration),
+            Box::new(m20261019_130000_action_journal::Migration),
+            Box::new(m20261019_140000_action_journal_reverts_entry::Migration),
+        ]
     }
 }
@@ -0,0 +1,40 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Existing rows were hashed with the original hasher, which is version 1
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ReferenceImage::Table)
+                    .add_column(integer(ReferenceImage::HashVersion).default(1))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ReferenceImage::Table)
+                    .drop_column(ReferenceImage::HashVersion)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ReferenceImage {
+    Table,
+    HashVersion,
+}
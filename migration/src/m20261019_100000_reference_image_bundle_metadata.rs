@@ -0,0 +1,76 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only supports adding a single column per statement
+        for column in [
+            string_null(ReferenceImage::Digest),
+            integer_null(ReferenceImage::Width),
+            integer_null(ReferenceImage::Height),
+            string_null(ReferenceImage::Collection),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ReferenceImage::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_reference_image_digest")
+                    .table(ReferenceImage::Table)
+                    .col(ReferenceImage::Digest)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_reference_image_digest")
+                    .table(ReferenceImage::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        for column in [
+            ReferenceImage::Digest,
+            ReferenceImage::Width,
+            ReferenceImage::Height,
+            ReferenceImage::Collection,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ReferenceImage::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ReferenceImage {
+    Table,
+    Digest,
+    Width,
+    Height,
+    Collection,
+}
@@ -0,0 +1,56 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Concurrent registrations could insert the same filepath more than once,
+        // keep the most recently inserted row so the unique index can be created
+        manager
+            .get_connection()
+            .execute(sea_orm::Statement::from_string(
+                manager.get_database_backend(),
+                r#"
+                DELETE FROM reference_image
+                WHERE id NOT IN (
+                    SELECT MAX(id) FROM reference_image GROUP BY filepath
+                )
+                "#,
+            ))
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_reference_image_filepath")
+                    .table(ReferenceImage::Table)
+                    .col(ReferenceImage::Filepath)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_reference_image_filepath")
+                    .table(ReferenceImage::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ReferenceImage {
+    Table,
+    Filepath,
+}
@@ -0,0 +1,43 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(QuarantineEntry::Table)
+                    .if_not_exists()
+                    .col(pk_auto(QuarantineEntry::Id))
+                    .col(string(QuarantineEntry::OriginalPath))
+                    .col(string(QuarantineEntry::QuarantinePath))
+                    .col(string(QuarantineEntry::Digest))
+                    .col(timestamp(QuarantineEntry::CreatedAt).default(Expr::current_timestamp()))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(QuarantineEntry::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum QuarantineEntry {
+    Table,
+    Id,
+    OriginalPath,
+    QuarantinePath,
+    Digest,
+    CreatedAt,
+}
@@ -0,0 +1,100 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ActionJournal::Table)
+                    .if_not_exists()
+                    .col(pk_auto(ActionJournal::Id))
+                    .col(string(ActionJournal::BatchId))
+                    .col(string(ActionJournal::Operation))
+                    .col(string(ActionJournal::Source))
+                    .col(string_null(ActionJournal::Destination))
+                    .col(string_null(ActionJournal::DigestBefore))
+                    .col(string_null(ActionJournal::DigestAfter))
+                    .col(string_null(ActionJournal::RevertsBatchId))
+                    .col(timestamp(ActionJournal::CreatedAt).default(Expr::current_timestamp()))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_action_journal_batch_id")
+                    .table(ActionJournal::Table)
+                    .col(ActionJournal::BatchId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // The journal is append-only
+        manager
+            .get_connection()
+            .execute(sea_orm::Statement::from_string(
+                manager.get_database_backend(),
+                r#"
+                CREATE TRIGGER IF NOT EXISTS action_journal_no_update
+                BEFORE UPDATE ON action_journal
+                BEGIN
+                    SELECT RAISE(ABORT, 'action_journal is append-only');
+                END
+                "#,
+            ))
+            .await?;
+
+        manager
+            .get_connection()
+            .execute(sea_orm::Statement::from_string(
+                manager.get_database_backend(),
+                r#"
+                CREATE TRIGGER IF NOT EXISTS action_journal_no_delete
+                BEFORE DELETE ON action_journal
+                BEGIN
+                    SELECT RAISE(ABORT, 'action_journal is append-only');
+                END
+                "#,
+            ))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for trigger in ["action_journal_no_update", "action_journal_no_delete"] {
+            manager
+                .get_connection()
+                .execute(sea_orm::Statement::from_string(
+                    manager.get_database_backend(),
+                    format!("DROP TRIGGER IF EXISTS {trigger}"),
+                ))
+                .await?;
+        }
+
+        manager
+            .drop_table(Table::drop().table(ActionJournal::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ActionJournal {
+    Table,
+    Id,
+    BatchId,
+    Operation,
+    Source,
+    Destination,
+    DigestBefore,
+    DigestAfter,
+    RevertsBatchId,
+    CreatedAt,
+}
@@ -0,0 +1,88 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ActionJournal::Table)
+                    .add_column(integer_null(ActionJournal::RevertsEntryId))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_action_journal_reverts_entry_id")
+                    .table(ActionJournal::Table)
+                    .col(ActionJournal::RevertsEntryId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Restores written before only name the batch, link them to the entry they reversed
+        // The journal is append-only, so the trigger is lifted for this one update
+        let connection = manager.get_connection();
+        let backend = manager.get_database_backend();
+        for sql in [
+            "DROP TRIGGER IF EXISTS action_journal_no_update",
+            r#"
+            UPDATE action_journal
+            SET reverts_entry_id = (
+                SELECT reversed.id FROM action_journal AS reversed
+                WHERE reversed.batch_id = action_journal.reverts_batch_id
+                    AND reversed.source = action_journal.destination
+                ORDER BY reversed.id DESC
+                LIMIT 1
+            )
+            WHERE operation = 'restore' AND reverts_batch_id IS NOT NULL
+            "#,
+            r#"
+            CREATE TRIGGER IF NOT EXISTS action_journal_no_update
+            BEFORE UPDATE ON action_journal
+            BEGIN
+                SELECT RAISE(ABORT, 'action_journal is append-only');
+            END
+            "#,
+        ] {
+            connection
+                .execute(sea_orm::Statement::from_string(backend, sql))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_action_journal_reverts_entry_id")
+                    .table(ActionJournal::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ActionJournal::Table)
+                    .drop_column(ActionJournal::RevertsEntryId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ActionJournal {
+    Table,
+    RevertsEntryId,
+}
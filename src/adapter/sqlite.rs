@@ -1,9 +1,13 @@
-use anyhow::{Context, Result};
-use sea_orm::{ConnectOptions, Database, DatabaseConnection};
-use std::time::Duration;
+use anyhow::{bail, Context, Result};
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{
+    sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    ConnectionTrait, DatabaseConnection, DbBackend, SqlxSqliteConnector, Statement,
+};
+use std::{collections::HashSet, path::Path, time::Duration};
 use tokio::sync::OnceCell;
 
-const DATABASE_URL: &str = "sqlite://db.sqlite?mode=rwc";
+use crate::settings;
 
 static DB: OnceCell<DatabaseConnection> = OnceCell::const_new();
 
@@ -12,20 +16,164 @@ pub async fn get_db() -> &'static DatabaseConnection {
         .await
 }
 
+// Open the database and bring its schema up to date, then close it again
+// This is run once at startup so that schema problems are reported before the window opens
+pub async fn check_database() -> Result<()> {
+    let db = establish_connection().await?;
+    db.close().await.context("Failed to close database")?;
+
+    // The logger is not running yet, so the warning goes to stderr like the errors
+    let database_path = settings::database_path(&settings::load()?)?;
+    if let Some(legacy_path) = settings::unused_legacy_database(&database_path) {
+        eprintln!(
+            "Warning: {} is no longer used, the database is now at {}. Move the file there to keep its data",
+            legacy_path.display(),
+            database_path.display()
+        );
+    }
+    Ok(())
+}
+
 async fn establish_connection() -> Result<DatabaseConnection> {
-    let mut opt = ConnectOptions::new(DATABASE_URL);
-    opt.connect_timeout(Duration::from_secs(5))
-        .acquire_timeout(Duration::from_secs(5))
-        .idle_timeout(Duration::from_secs(5))
-        .max_lifetime(Duration::from_secs(5))
-        .sqlx_logging(true)
-        .sqlx_logging_level(log::LevelFilter::Debug);
+    let database_path = settings::database_path(&settings::load()?)?;
+    if let Some(parent) = database_path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
 
-    let db = Database::connect(opt)
+    // The path is passed as is, a URL would cut it at `?` or `#`
+    use sea_orm::sqlx::ConnectOptions;
+    let connect_options = SqliteConnectOptions::new()
+        .filename(&database_path)
+        .create_if_missing(true)
+        .log_statements(log::LevelFilter::Debug);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .acquire_timeout(Duration::from_secs(5))
+        .idle_timeout(Some(Duration::from_secs(5)))
+        .max_lifetime(Some(Duration::from_secs(5)))
+        .connect_with(connect_options)
         .await
         .with_context(|| "Failed to create database connection")?;
+    let db = SqlxSqliteConnector::from_sqlx_sqlite_pool(pool);
 
     db.ping().await.with_context(|| "Failed to ping database")?;
 
+    migrate(&db, &database_path).await?;
+
     Ok(db)
 }
+
+// Apply the pending migrations, backing up the database first when it already has a schema
+async fn migrate(db: &DatabaseConnection, database_path: &Path) -> Result<()> {
+    let (applied_migrations, pending_migrations) = check_schema(db).await?;
+    if pending_migrations.is_empty() {
+        return Ok(());
+    }
+
+    if !applied_migrations.is_empty() {
+        let mut backup_name = database_path
+            .file_name()
+            .context("Invalid database path")?
+            .to_os_string();
+        backup_name.push(format!(
+            ".backup-{}",
+            chrono::Local::now().format("%Y%m%d%H%M%S")
+        ));
+        let backup_path = database_path.with_file_name(backup_name);
+        // VACUUM INTO writes a consistent copy even while the database is open
+        db.execute(Statement::from_string(
+            DbBackend::Sqlite,
+            format!(
+                "VACUUM INTO '{}'",
+                backup_path.to_string_lossy().replace('\'', "''")
+            ),
+        ))
+        .await
+        .with_context(|| format!("Failed to back up database to {}", backup_path.display()))?;
+    }
+
+    Migrator::up(db, None)
+        .await
+        .context("Failed to apply database migrations")?;
+
+    Ok(())
+}
+
+// Returns the applied and the pending migrations
+// Fails when the database was migrated by a newer version of the application
+async fn check_schema(db: &DatabaseConnection) -> Result<(Vec<String>, Vec<String>)> {
+    let has_migration_table = db
+        .query_one(Statement::from_string(
+            DbBackend::Sqlite,
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'seaql_migrations'",
+        ))
+        .await?
+        .is_some();
+    let applied_migrations = if has_migration_table {
+        db.query_all(Statement::from_string(
+            DbBackend::Sqlite,
+            "SELECT version FROM seaql_migrations ORDER BY version",
+        ))
+        .await?
+        .into_iter()
+        .map(|row| row.try_get::<String>("", "version"))
+        .collect::<Result<Vec<_>, _>>()?
+    } else {
+        Vec::new()
+    };
+
+    let known_migrations = Migrator::migrations()
+        .iter()
+        .map(|m| m.name().to_string())
+        .collect::<Vec<_>>();
+
+    let unknown_migrations = applied_migrations
+        .iter()
+        .filter(|m| !known_migrations.contains(m))
+        .cloned()
+        .collect::<Vec<_>>();
+    if !unknown_migrations.is_empty() {
+        bail!(
+            "The database schema is newer than this version of imgdup supports. Please update imgdup (unknown migrations: {})",
+            unknown_migrations.join(", ")
+        );
+    }
+
+    let applied = applied_migrations.iter().collect::<HashSet<_>>();
+    let pending_migrations = known_migrations
+        .iter()
+        .filter(|m| !applied.contains(m))
+        .cloned()
+        .collect();
+
+    Ok((applied_migrations, pending_migrations))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::Database;
+
+    #[tokio::test]
+    async fn test_check_schema() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+
+        let (applied, pending) = check_schema(&db).await.unwrap();
+        assert!(applied.is_empty());
+        assert_eq!(pending.len(), Migrator::migrations().len());
+
+        Migrator::up(&db, None).await.unwrap();
+        let (applied, pending) = check_schema(&db).await.unwrap();
+        assert_eq!(applied.len(), Migrator::migrations().len());
+        assert!(pending.is_empty());
+
+        db.execute(Statement::from_string(
+            DbBackend::Sqlite,
+            "INSERT INTO seaql_migrations (version, applied_at) VALUES ('m29991231_000000_future', 0)",
+        ))
+        .await
+        .unwrap();
+        assert!(check_schema(&db).await.is_err());
+    }
+}
@@ -1,219 +1,582 @@
-use std::{
-    collections::{BTreeMap, HashMap},
-    path::Path,
-    sync::Mutex,
-};
+use std::collections::BTreeMap;
 
-use anyhow::{Context, Result};
 use dioxus::prelude::{
     server_fn::codec::{JsonStream, StreamingJson},
     *,
 };
-use sea_orm::IntoActiveModel;
 use serde::{Deserialize, Serialize};
 
-use crate::{di::get_container, image_processing, models};
+use crate::{bundle, models, report::ReportFormat};
+
+// The bodies of the server functions and their helpers only exist in the server build
+#[cfg(feature = "server")]
+use {
+    crate::{
+        di::{get_container, Container},
+        file_operations, image_processing,
+        search_session::SearchSession,
+        services, settings,
+    },
+    anyhow::{Context, Result},
+    std::{collections::HashMap, path::Path},
+};
 
 #[server]
 pub async fn search_similar_images(
     selected_directory: String,
 ) -> Result<BTreeMap<u32, models::SimilarImage>, ServerFnError> {
-    if selected_directory == "" {
+    if selected_directory.is_empty() {
         return Ok(BTreeMap::<u32, models::SimilarImage>::new());
     }
 
-    let reference_image_repository = get_container().await.reference_image_repository.clone();
-    let reference_images = reference_image_repository.find_all().await?;
-    let reference_hashes = reference_images
-        .iter()
-        .map(|i| img_hash::ImageHash::from_bytes(&i.hash).unwrap())
-        .collect::<Vec<_>>();
+    let settings = settings::load().map_err(|e| ServerFnError::new(format!("{e:#}")))?;
+    let similar_images = services::search::find_similar_images(
+        get_container().await,
+        std::slice::from_ref(&selected_directory),
+        &settings,
+    )
+    .await
+    .map_err(|e| ServerFnError::new(format!("{e:#}")))?
+    .into_iter()
+    .enumerate()
+    .map(|(i, similar_image)| (i as u32, similar_image))
+    .collect::<BTreeMap<_, _>>();
 
-    if reference_hashes.is_empty() {
-        return Err(ServerFnError::new("No reference images registered"));
-    }
+    // Only files which could be fingerprinted can be deleted later
+    let matched_files = similar_images
+        .values()
+        .filter_map(|i| Some((i.filepath.clone(), i.fingerprint.clone()?)))
+        .collect::<HashMap<_, _>>();
+    let search_session = SearchSession::new(Path::new(&selected_directory), matched_files)
+        .map_err(|e| ServerFnError::new(format!("{e:#}")))?;
+    let container = get_container().await;
+    *container.search_session.write().await = Some(search_session);
+    container.thumbnail_service.prefetch(
+        similar_images
+            .values()
+            .map(|i| i.filepath.clone())
+            .collect(),
+        services::thumbnails::DEFAULT_THUMBNAIL_SIZE,
+    );
 
-    let target_files = match scan_images(&selected_directory) {
-        Ok(v) => v,
-        Err(e) => return Err(ServerFnError::new(e.to_string())),
-    };
+    Ok(similar_images)
+}
 
-    use rayon::prelude::*;
+// Write the results into a file which can be shared with people who don't run imgdup
+#[server]
+pub async fn export_search_results(
+    report: models::SearchReport,
+    format: ReportFormat,
+    output_directory: String,
+) -> Result<String, ServerFnError> {
+    let report_path = services::search::export_results(
+        get_container().await,
+        &report,
+        format,
+        Path::new(&output_directory),
+    )
+    .await
+    .map_err(|e| ServerFnError::new(format!("{e:#}")))?;
 
-    let mut calc_results = target_files
-        .par_iter()
-        .filter_map(|filepath| {
-            let hash = image_processing::compute_hash(filepath).unwrap();
-            let max_similarity = reference_hashes
-                .iter()
-                .map(|ref_hash| image_processing::calculate_similarity(ref_hash, &hash))
-                .fold(0u32, |a, b| a.max(b));
-            Some((filepath, max_similarity))
-        })
-        .collect::<Vec<_>>();
+    Ok(report_path.to_string_lossy().to_string())
+}
 
-    let mut similar_images = BTreeMap::<u32, models::SimilarImage>::new();
-    calc_results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-    for (i, (file, sim)) in calc_results
-        .into_iter()
-        .filter(|(_, s)| *s >= 90)
-        .take(10)
-        .enumerate()
-    {
-        similar_images.insert(
-            i as u32,
-            models::SimilarImage {
-                filepath: format!("{}", file.to_string()),
-                similarity: sim,
-                is_deleted: false,
-                error_message: None,
-            },
-        );
+// Details of a search result and its matched reference for the comparison view
+#[server]
+pub async fn compare_images(
+    filepath: String,
+    reference: Option<String>,
+) -> Result<models::ImageComparison, ServerFnError> {
+    let container = get_container().await;
+    for path in std::iter::once(&filepath).chain(reference.as_ref()) {
+        let is_allowed = services::thumbnails::is_allowed(container, Path::new(path))
+            .await
+            .map_err(|e| ServerFnError::new(format!("{e:#}")))?;
+        if !is_allowed {
+            return Err(ServerFnError::new(format!(
+                "Not part of the search result: {path}"
+            )));
+        }
     }
 
-    Ok(similar_images)
+    let comparison = tokio::task::spawn_blocking(move || {
+        let hash_distance = reference.as_deref().and_then(|reference| {
+            let hash = image_processing::compute_hash(&filepath).ok()?;
+            let reference_hash = image_processing::compute_hash(reference).ok()?;
+            Some(hash.dist(&reference_hash))
+        });
+        models::ImageComparison {
+            candidate: image_details(&filepath),
+            reference: reference.as_deref().map(image_details),
+            hash_distance,
+        }
+    })
+    .await
+    .map_err(|e| ServerFnError::new(e.to_string()))?;
+
+    Ok(comparison)
 }
 
-// Collect the image paths from the given directory
-// It will look for the images in the subdirectories as well
-// It will collect the first 5 images and the last 5 images for each directory
-fn scan_images(directory: &str) -> Result<Vec<String>> {
-    let mut targets = Vec::new();
-    for entry in walkdir::WalkDir::new(directory)
-        .into_iter()
-        .filter_map(Result::ok)
-        .filter(|e| e.file_type().is_dir())
-    {
-        let mut imgs = std::fs::read_dir(entry.path())
-            .with_context(|| format!("Failed to read directory: {}", entry.path().display()))?
-            .filter_map(Result::ok)
-            .filter(|d| {
-                matches!(
-                    d.path()
-                        .extension()
-                        .unwrap_or_default()
-                        .to_ascii_lowercase()
-                        .to_str()
-                        .unwrap_or_default(),
-                    "jpg" | "jpeg" | "png"
-                )
-            })
-            .map(|d| d.path().to_string_lossy().to_string())
-            .collect::<Vec<_>>();
+#[cfg(feature = "server")]
+fn image_details(path: &str) -> models::ImageDetails {
+    let (width, height) = image_processing::image_dimensions(path).ok().unzip();
+    let (taken_at, camera) = image_processing::read_exif(path);
+    models::ImageDetails {
+        filepath: path.to_string(),
+        width,
+        height,
+        size: std::fs::metadata(path).ok().map(|m| m.len()),
+        format: image_processing::image_format(path).ok(),
+        taken_at,
+        camera,
+    }
+}
 
-        imgs.sort();
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SettingsInfo {
+    // Settings as stored in the config file, without the overrides
+    pub settings: models::settings::Settings,
+    pub config_path: String,
+    pub database_path: String,
+    // Delete methods supported on the machine of the server
+    pub delete_methods: Vec<models::DeleteMethod>,
+}
 
-        let selected = imgs
-            .iter()
-            .take(5)
-            .chain(imgs.iter().rev().take(5))
-            .cloned()
-            .collect::<Vec<_>>();
+#[server]
+pub async fn get_settings() -> Result<SettingsInfo, ServerFnError> {
+    let info = settings_info().map_err(|e| ServerFnError::new(format!("{e:#}")))?;
+    Ok(info)
+}
 
-        targets.extend(selected);
-    }
+#[cfg(feature = "server")]
+fn settings_info() -> Result<SettingsInfo> {
+    let settings = settings::load_persisted()?;
+    Ok(SettingsInfo {
+        config_path: settings::config_path()?.to_string_lossy().to_string(),
+        database_path: settings::database_path(&settings)?
+            .to_string_lossy()
+            .to_string(),
+        delete_methods: models::DeleteMethod::ALL
+            .into_iter()
+            .filter(|m| m.is_supported())
+            .collect(),
+        settings,
+    })
+}
 
-    Ok(targets)
+#[server]
+pub async fn save_settings(settings: models::settings::Settings) -> Result<(), ServerFnError> {
+    settings::save(&settings).map_err(|e| ServerFnError::new(format!("{e:#}")))?;
+    Ok(())
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum RegistrationEvent {
+    Hashed,
+    Inserted,
+    Updated,
+    Failed,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RegistrationProgress {
+    pub filepath: String,
+    pub event: RegistrationEvent,
+    pub processed: usize,
+    pub total: usize,
+    pub message: Option<String>,
+}
+
+// Every file is hashed first, then the hashed files are saved in one transaction
+// Files which are not images fail one by one, the others are still registered
+#[server(output = StreamingJson)]
+pub async fn register_reference_images_stream(
+    selected_files: Vec<String>,
+    collection: Option<String>,
+) -> Result<JsonStream<RegistrationProgress>, ServerFnError> {
+    let container = get_container().await;
+
+    let (tx, rx) = futures::channel::mpsc::unbounded();
+    tokio::spawn(async move {
+        let total = selected_files.len();
+        let processed = std::sync::atomic::AtomicUsize::new(0);
+        let on_hashed = {
+            let tx = tx.clone();
+            move |result: Result<
+                &models::reference_image::ReferenceImageInput,
+                &models::reference_image::RejectedFile,
+            >| {
+                let processed = processed.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1;
+                let progress = match result {
+                    Ok(reference_image) => RegistrationProgress {
+                        filepath: reference_image.filepath.clone(),
+                        event: RegistrationEvent::Hashed,
+                        processed,
+                        total,
+                        message: None,
+                    },
+                    Err(rejected_file) => RegistrationProgress {
+                        filepath: rejected_file.filepath.clone(),
+                        event: RegistrationEvent::Failed,
+                        processed,
+                        total,
+                        message: Some(rejected_file.reason.clone()),
+                    },
+                };
+                // The client may have gone away, which does not stop the registration
+                let _ = tx.unbounded_send(Ok(progress));
+            }
+        };
+
+        let summary = match services::reference_images::register(
+            container,
+            selected_files,
+            collection,
+            on_hashed,
+        )
+        .await
+        {
+            Ok((summary, _)) => summary,
+            Err(e) => {
+                let _ = tx.unbounded_send(Err(ServerFnError::new(format!("{e:#}"))));
+                return;
+            }
+        };
+
+        container.thumbnail_service.prefetch(
+            summary
+                .inserted
+                .iter()
+                .chain(&summary.updated)
+                .cloned()
+                .collect(),
+            services::thumbnails::DEFAULT_THUMBNAIL_SIZE,
+        );
+        let events = summary
+            .inserted
+            .into_iter()
+            .map(|filepath| (filepath, RegistrationEvent::Inserted))
+            .chain(
+                summary
+                    .updated
+                    .into_iter()
+                    .map(|filepath| (filepath, RegistrationEvent::Updated)),
+            );
+        for (filepath, event) in events {
+            let progress = RegistrationProgress {
+                filepath,
+                event,
+                processed: total,
+                total,
+                message: None,
+            };
+            if tx.unbounded_send(Ok(progress)).is_err() {
+                return;
+            }
+        }
+    });
+
+    Ok(JsonStream::<RegistrationProgress>::new(rx))
+}
+
+// Token the UI appends to thumbnail URLs
 #[server]
-pub async fn register_reference_images(selected_files: Vec<String>) -> Result<(), ServerFnError> {
-    if selected_files.is_empty() {
-        return Ok(());
+pub async fn get_thumbnail_token() -> Result<String, ServerFnError> {
+    Ok(crate::thumbnail_route::token().to_string())
+}
+
+// The search root has to be an existing directory
+#[server]
+pub async fn check_search_directory(directory: String) -> Result<(), ServerFnError> {
+    if !Path::new(&directory).is_dir() {
+        return Err(ServerFnError::new(format!("Not a directory: {directory}")));
     }
+    Ok(())
+}
+
+#[server]
+pub async fn get_reference_image_page(
+    query: models::reference_image::ReferenceImageQuery,
+) -> Result<models::reference_image::ReferenceImagePage, ServerFnError> {
+    let page = services::reference_images::find_page(get_container().await, &query)
+        .await
+        .map_err(|e| ServerFnError::new(format!("{e:#}")))?;
+    Ok(page)
+}
 
+#[server]
+pub async fn delete_registered_reference_images(ids: Vec<i32>) -> Result<u64, ServerFnError> {
     let reference_image_repository = get_container().await.reference_image_repository.clone();
-    let existing_images = reference_image_repository
-        .find_by_filepaths(&selected_files)
+    let deleted = reference_image_repository.delete_many(&ids).await?;
+    Ok(deleted)
+}
+
+// Move the reference images into the collection, or out of every collection when it is `None`
+#[server]
+pub async fn move_reference_images_to_collection(
+    ids: Vec<i32>,
+    collection: Option<String>,
+) -> Result<u64, ServerFnError> {
+    let reference_image_repository = get_container().await.reference_image_repository.clone();
+    let updated = reference_image_repository
+        .update_collection(&ids, collection)
         .await?;
-    let existing_filepaths = existing_images
-        .iter()
-        .map(|i| (i.filepath.clone(), i.clone().into_active_model()))
-        .collect::<HashMap<String, entity::reference_image::ActiveModel>>();
-
-    use rayon::prelude::*;
-    let selected_file_hashmap = Mutex::new(HashMap::new());
-    selected_files.par_iter().for_each(|selected_file| {
-        let hash = image_processing::compute_hash(selected_file)
-            .unwrap()
-            .as_bytes()
-            .to_vec();
-        selected_file_hashmap
-            .lock()
-            .unwrap()
-            .insert(selected_file.as_str(), hash);
-    });
-    let selected_file_hashmap = selected_file_hashmap.into_inner().unwrap();
-
-    let mut new_images = Vec::new();
-    for selected_file in selected_files.clone() {
-        let hash = selected_file_hashmap
-            .get(selected_file.as_str())
-            .unwrap()
-            .to_vec();
-        if let Some(v) = existing_filepaths.get(selected_file.as_str()) {
-            let mut v = v.clone();
-            v.hash = sea_orm::Set(hash);
-            reference_image_repository.update(v).await?;
-        } else {
-            new_images.push(models::reference_image::ReferenceImageInput {
-                filepath: selected_file,
-                hash,
-            });
-        }
-    }
+    Ok(updated)
+}
 
-    reference_image_repository.create_many(new_images).await?;
+#[server]
+pub async fn get_reference_collections() -> Result<Vec<String>, ServerFnError> {
+    let reference_image_repository = get_container().await.reference_image_repository.clone();
+    let collections = reference_image_repository.find_collections().await?;
+    Ok(collections)
+}
 
-    Ok(())
+#[server]
+pub async fn export_reference_bundle(
+    reference_image_ids: Vec<i32>,
+    collections: Vec<String>,
+    output_directory: String,
+    encoding: bundle::BundleEncoding,
+    include_thumbnails: bool,
+) -> Result<String, ServerFnError> {
+    let bundle_path = services::reference_images::export_bundle(
+        get_container().await,
+        &reference_image_ids,
+        &collections,
+        Path::new(&output_directory),
+        encoding,
+        include_thumbnails,
+    )
+    .await
+    .map_err(|e| ServerFnError::new(format!("{e:#}")))?;
+
+    Ok(bundle_path.to_string_lossy().to_string())
 }
 
 #[server]
-pub async fn get_registered_reference_images(
-) -> Result<Vec<entity::reference_image::Model>, ServerFnError> {
+pub async fn import_reference_bundle(
+    bundle_path: String,
+) -> Result<bundle::ImportReport, ServerFnError> {
+    let report =
+        services::reference_images::import_bundle(get_container().await, Path::new(&bundle_path))
+            .await
+            .map_err(|e| ServerFnError::new(format!("{e:#}")))?;
+
+    Ok(report)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RehashProgress {
+    pub reference_image_id: i32,
+    pub filepath: String,
+    pub processed: usize,
+    pub total: usize,
+    pub is_success: bool,
+    pub message: Option<String>,
+}
+
+// Recompute the hashes of the given reference images, or of all of them when no id is given,
+// from their source files with the current hasher version
+#[server(output = StreamingJson)]
+pub async fn rehash_reference_images_stream(
+    reference_image_ids: Vec<i32>,
+) -> Result<JsonStream<RehashProgress>, ServerFnError> {
     let reference_image_repository = get_container().await.reference_image_repository.clone();
-    let reference_images = reference_image_repository.find_all().await?;
-    Ok(reference_images)
+    let reference_images = if reference_image_ids.is_empty() {
+        reference_image_repository.find_all().await?
+    } else {
+        reference_image_repository
+            .find_by_ids(&reference_image_ids)
+            .await?
+    };
+
+    let (tx, rx) = futures::channel::mpsc::unbounded();
+    tokio::spawn(async move {
+        let total = reference_images.len();
+        for (i, reference_image) in reference_images.into_iter().enumerate() {
+            let mut progress = RehashProgress {
+                reference_image_id: reference_image.id,
+                filepath: reference_image.filepath.clone(),
+                processed: i + 1,
+                total,
+                is_success: false,
+                message: None,
+            };
+
+            let filepath = reference_image.filepath.clone();
+            let hash =
+                tokio::task::spawn_blocking(move || image_processing::compute_hash(&filepath))
+                    .await
+                    .map_err(anyhow::Error::from)
+                    .and_then(|r| r);
+            match hash {
+                Ok(hash) => {
+                    match reference_image_repository
+                        .update_hash(
+                            reference_image.id,
+                            hash.as_bytes().to_vec(),
+                            image_processing::HASH_VERSION,
+                        )
+                        .await
+                    {
+                        Ok(_) => {
+                            progress.is_success = true;
+                        }
+                        Err(e) => {
+                            progress.message = Some(e.to_string());
+                        }
+                    }
+                }
+                Err(e) => {
+                    progress.message = Some(format!("{e:#}"));
+                }
+            }
+
+            if tx.unbounded_send(Ok(progress)).is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(JsonStream::<RehashProgress>::new(rx))
 }
 
+// Check what would happen to each selected image without touching any file
+// An image passes when it still exists, is writable and has not changed since the search
 #[server]
-pub async fn delete_registered_reference_image(id: i32) -> Result<(), ServerFnError> {
-    let reference_image_repository = get_container().await.reference_image_repository.clone();
-    reference_image_repository.delete(id).await?;
-    Ok(())
+pub async fn dry_run_delete_similar_images(
+    selected_images: Vec<(u32, String)>,
+    method: models::DeleteMethod,
+    quarantine_directory: Option<String>,
+) -> Result<Vec<models::DryRunReport>, ServerFnError> {
+    let search_session = current_search_session().await?;
+
+    let reports = tokio::task::spawn_blocking(move || {
+        use rayon::prelude::*;
+        selected_images
+            .into_par_iter()
+            .map(|(image_id, selected_image)| {
+                let mut report = models::DryRunReport {
+                    image_id,
+                    filepath: selected_image.clone(),
+                    fingerprint: None,
+                    is_ok: false,
+                    action: String::new(),
+                    message: None,
+                };
+                match dry_run_delete(
+                    &search_session,
+                    &selected_image,
+                    method,
+                    quarantine_directory.as_deref().map(Path::new),
+                ) {
+                    Ok((action, fingerprint)) => {
+                        report.is_ok = true;
+                        report.action = action;
+                        report.fingerprint = Some(fingerprint);
+                    }
+                    Err(e) => {
+                        report.message = Some(format!("{e:#}"));
+                    }
+                }
+                report
+            })
+            .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|e| ServerFnError::new(e.to_string()))?;
+
+    Ok(reports)
+}
+
+#[cfg(feature = "server")]
+fn dry_run_delete(
+    search_session: &SearchSession,
+    selected_image: &str,
+    method: models::DeleteMethod,
+    quarantine_directory: Option<&Path>,
+) -> Result<(String, models::FileFingerprint)> {
+    let path = Path::new(selected_image);
+    file_operations::check_writable(path)?;
+    search_session.verify(selected_image)?;
+
+    let action = file_operations::describe_delete(
+        path,
+        method,
+        quarantine_directory,
+        search_session.root(),
+    )?;
+    let fingerprint = search_session.expected_fingerprint(selected_image)?.clone();
+    Ok((action, fingerprint))
+}
+
+#[cfg(feature = "server")]
+async fn current_search_session() -> Result<SearchSession, ServerFnError> {
+    get_container()
+        .await
+        .search_session
+        .read()
+        .await
+        .clone()
+        .ok_or_else(|| ServerFnError::new("No search result. Please search again"))
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DeleteProgress {
+    // Journal batch of the request, which can be undone with `undo_batch`
+    pub batch_id: String,
     pub image_id: u32,
+    pub method: models::DeleteMethod,
     pub is_success: bool,
     pub message: Option<String>,
 }
 
+// Only the files a dry run confirmed are deleted, and only while they are unchanged
 #[server(output = StreamingJson)]
 pub async fn delete_similar_images_stream(
-    selected_images: Vec<(u32, String)>,
+    selected_images: Vec<(u32, String, models::FileFingerprint)>,
+    method: models::DeleteMethod,
+    quarantine_directory: Option<String>,
 ) -> Result<JsonStream<DeleteProgress>, ServerFnError> {
+    let container = get_container().await;
+    let search_session = current_search_session().await?;
+
     let (tx, rx) = futures::channel::mpsc::unbounded();
     tokio::spawn(async move {
-        for (image_id, selected_image) in selected_images {
+        // Every file of this request belongs to the same journal batch, so it can be undone at once
+        let batch_id = services::deletion::new_batch_id();
+        let quarantine_directory = quarantine_directory.as_deref().map(Path::new);
+        for (image_id, selected_image, fingerprint) in selected_images {
             let path = Path::new(&selected_image);
             let mut progress = DeleteProgress {
+                batch_id: batch_id.clone(),
                 image_id,
+                method,
                 is_success: false,
                 message: None,
             };
-            if path.exists() {
-                match std::fs::remove_file(path) {
+            if !path.exists() {
+                progress.message = Some("File not found".to_string());
+            } else if let Err(e) = search_session.verify_confirmed(&selected_image, &fingerprint) {
+                progress.message = Some(format!("{e:#}"));
+            } else {
+                let result = services::deletion::delete_similar_image(
+                    container,
+                    &batch_id,
+                    path,
+                    method,
+                    quarantine_directory,
+                    search_session.root(),
+                )
+                .await;
+                match result {
                     Ok(_) => {
                         progress.is_success = true;
                     }
                     Err(e) => {
-                        progress.message = Some(e.to_string());
+                        progress.message = Some(format!("{e:#}"));
                     }
                 }
-            } else {
-                progress.message = Some("File not found".to_string());
             }
 
             if tx.unbounded_send(Ok(progress)).is_err() {
@@ -225,6 +588,397 @@ pub async fn delete_similar_images_stream(
     Ok(JsonStream::<DeleteProgress>::new(rx))
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LinkProgress {
+    pub image_id: u32,
+    pub method: models::LinkMethod,
+    pub is_success: bool,
+    pub message: Option<String>,
+}
+
+// Replace each selected image with a link to its target, which is either
+// a registered reference image or another image of the search result
+#[server(output = StreamingJson)]
+pub async fn link_similar_images_stream(
+    selected_images: Vec<(u32, String, String)>,
+    method: models::LinkMethod,
+    // Link files whose contents differ from the target, which loses their content for good
+    allow_different_content: bool,
+) -> Result<JsonStream<LinkProgress>, ServerFnError> {
+    let container = get_container().await;
+    let search_session = current_search_session().await?;
+
+    let targets = selected_images
+        .iter()
+        .map(|(_, _, target)| target.clone())
+        .collect::<Vec<_>>();
+    let reference_filepaths = container
+        .reference_image_repository
+        .find_by_filepaths(&targets)
+        .await?
+        .into_iter()
+        .map(|i| i.filepath)
+        .collect::<std::collections::HashSet<_>>();
+
+    let (tx, rx) = futures::channel::mpsc::unbounded();
+    tokio::spawn(async move {
+        let batch_id = services::deletion::new_batch_id();
+        for (image_id, selected_image, target) in selected_images {
+            let mut progress = LinkProgress {
+                image_id,
+                method,
+                is_success: false,
+                message: None,
+            };
+
+            let verified = search_session.verify(&selected_image).and_then(|_| {
+                if search_session.contains(&target) {
+                    search_session.verify(&target)
+                } else if reference_filepaths.contains(&target) {
+                    Ok(())
+                } else {
+                    anyhow::bail!(
+                        "The link target is neither a reference image nor a search result"
+                    )
+                }
+            });
+            let result = match verified {
+                Ok(_) => {
+                    link_similar_image(
+                        container,
+                        &batch_id,
+                        Path::new(&selected_image),
+                        Path::new(&target),
+                        method,
+                        allow_different_content,
+                    )
+                    .await
+                }
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(_) => {
+                    progress.is_success = true;
+                }
+                Err(e) => {
+                    progress.message = Some(format!("{e:#}"));
+                }
+            }
+
+            if tx.unbounded_send(Ok(progress)).is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(JsonStream::<LinkProgress>::new(rx))
+}
+
+#[cfg(feature = "server")]
+async fn link_similar_image(
+    container: &Container,
+    batch_id: &str,
+    path: &Path,
+    target: &Path,
+    method: models::LinkMethod,
+    allow_different_content: bool,
+) -> Result<()> {
+    let digest_before = image_processing::compute_digest(&path.to_string_lossy())?;
+    let target_digest = image_processing::compute_digest(&target.to_string_lossy())?;
+    // Links can not be undone, so only identical files are linked unless asked otherwise
+    if digest_before != target_digest && !allow_different_content {
+        anyhow::bail!("The image and the link target have different contents");
+    }
+
+    file_operations::link_file(path, target, method)?;
+    let digest_after = image_processing::compute_digest(&path.to_string_lossy())?;
+
+    container
+        .action_journal_repository
+        .append_many(vec![models::action_journal::ActionJournalInput {
+            batch_id: batch_id.to_string(),
+            operation: models::action_journal::FileOperation::Link,
+            source: path.to_string_lossy().to_string(),
+            destination: Some(target.to_string_lossy().to_string()),
+            digest_before: Some(digest_before),
+            digest_after: Some(digest_after),
+            reverts_batch_id: None,
+            reverts_entry_id: None,
+        }])
+        .await
+        .context("Failed to record the operation in the journal")?;
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct UndoReport {
+    pub batch_id: Option<String>,
+    pub restored: Vec<String>,
+    pub failures: Vec<UndoFailure>,
+    // The files were handled, but the undo could not be recorded
+    pub journal_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UndoFailure {
+    pub source: String,
+    pub reason: String,
+}
+
+// Number of journal entries shown on the history page
+#[cfg(feature = "server")]
+const HISTORY_LIMIT: u64 = 500;
+
+#[server]
+pub async fn get_action_history() -> Result<Vec<entity::action_journal::Model>, ServerFnError> {
+    let action_journal_repository = get_container().await.action_journal_repository.clone();
+    let entries = action_journal_repository.find_recent(HISTORY_LIMIT).await?;
+    Ok(entries)
+}
+
+// Undo the most recent batch of deletions, and report the files which can not be restored
+#[server]
+pub async fn undo_last_batch() -> Result<UndoReport, ServerFnError> {
+    let container = get_container().await;
+    match container
+        .action_journal_repository
+        .find_last_undoable_batch_id()
+        .await?
+    {
+        Some(batch_id) => undo(container, batch_id).await,
+        None => Ok(UndoReport::default()),
+    }
+}
+
+// Undo the given batch, for example from the toast shown after it was made
+// Files a previous undo failed on are tried again
+#[server]
+pub async fn undo_batch(batch_id: String) -> Result<UndoReport, ServerFnError> {
+    undo(get_container().await, batch_id).await
+}
+
+#[cfg(feature = "server")]
+async fn undo(container: &Container, batch_id: String) -> Result<UndoReport, ServerFnError> {
+    use models::action_journal::{ActionJournalInput, FileOperation};
+
+    let action_journal_repository = container.action_journal_repository.clone();
+
+    let entries = action_journal_repository
+        .find_unrestored_by_batch_id(&batch_id)
+        .await?;
+    if entries.is_empty() {
+        return Err(ServerFnError::new(
+            "The batch was already undone or can not be undone",
+        ));
+    }
+
+    // Failed attempts are journaled too, so the next undo goes on to the batch before
+    let mut report = UndoReport::default();
+    let mut journal_entries = Vec::new();
+    let undo_batch_id = services::deletion::new_batch_id();
+    for entry in entries.into_iter().rev() {
+        let result = reverse_journal_entry(container, &entry).await;
+        journal_entries.push(ActionJournalInput {
+            batch_id: undo_batch_id.clone(),
+            operation: if result.is_ok() {
+                FileOperation::Restore
+            } else {
+                FileOperation::RestoreFailed
+            },
+            source: entry.destination.clone().unwrap_or_default(),
+            destination: Some(entry.source.clone()),
+            digest_before: entry.digest_after.clone(),
+            digest_after: entry.digest_after.clone(),
+            reverts_batch_id: Some(batch_id.clone()),
+            reverts_entry_id: Some(entry.id),
+        });
+        match result {
+            Ok(_) => report.restored.push(entry.source),
+            Err(e) => report.failures.push(UndoFailure {
+                source: entry.source,
+                reason: format!("{e:#}"),
+            }),
+        }
+    }
+
+    // The files were already moved, so a journal error is reported instead of failing the undo
+    if let Err(e) = action_journal_repository.append_many(journal_entries).await {
+        report.journal_error = Some(format!("Failed to record the undo in the journal: {e}"));
+    }
+    report.batch_id = Some(batch_id);
+
+    Ok(report)
+}
+
+#[cfg(feature = "server")]
+async fn reverse_journal_entry(
+    container: &Container,
+    entry: &entity::action_journal::Model,
+) -> Result<()> {
+    use models::action_journal::FileOperation;
+
+    let operation = FileOperation::parse(&entry.operation)
+        .with_context(|| format!("Unknown operation: {}", entry.operation))?;
+    if operation == FileOperation::Delete {
+        anyhow::bail!("The file was deleted permanently");
+    }
+    if operation == FileOperation::Link {
+        anyhow::bail!("The file was replaced with a link");
+    }
+
+    let destination = entry
+        .destination
+        .as_deref()
+        .context("The destination of the operation is unknown")?;
+    let source = Path::new(&entry.source);
+    if source.exists() {
+        anyhow::bail!("A file already exists at the original location");
+    }
+    if !Path::new(destination).exists() {
+        anyhow::bail!("The file is no longer at {destination}");
+    }
+    if entry.digest_after.as_deref()
+        != Some(image_processing::compute_digest(destination)?.as_str())
+    {
+        anyhow::bail!("The file at {destination} was overwritten or modified");
+    }
+
+    match operation {
+        FileOperation::Trash => {
+            file_operations::restore_from_trash(Path::new(destination), source)?;
+        }
+        FileOperation::Quarantine => {
+            file_operations::quarantine::restore_from_quarantine(Path::new(destination), source)?;
+            if let Some(quarantine_entry) = container
+                .quarantine_entry_repository
+                .find_by_quarantine_path(destination)
+                .await?
+            {
+                container
+                    .quarantine_entry_repository
+                    .delete(quarantine_entry.id)
+                    .await?;
+            }
+        }
+        FileOperation::Delete
+        | FileOperation::Restore
+        | FileOperation::RestoreFailed
+        | FileOperation::Link => {
+            anyhow::bail!("The operation can not be reversed");
+        }
+    }
+
+    Ok(())
+}
+
+#[server]
+pub async fn get_quarantine_entries() -> Result<Vec<entity::quarantine_entry::Model>, ServerFnError>
+{
+    let quarantine_entry_repository = get_container().await.quarantine_entry_repository.clone();
+    let quarantine_entries = quarantine_entry_repository.find_all().await?;
+    Ok(quarantine_entries)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QuarantineResult {
+    pub quarantine_entry_id: i32,
+    pub is_success: bool,
+    pub message: Option<String>,
+}
+
+// Move the quarantined files back to their original locations
+#[server]
+pub async fn restore_quarantine_entries(
+    quarantine_entry_ids: Vec<i32>,
+) -> Result<Vec<QuarantineResult>, ServerFnError> {
+    let quarantine_entry_repository = get_container().await.quarantine_entry_repository.clone();
+    let quarantine_entries = quarantine_entry_repository
+        .find_by_ids(&quarantine_entry_ids)
+        .await?;
+
+    let mut results = Vec::new();
+    for quarantine_entry in quarantine_entries {
+        let mut result = QuarantineResult {
+            quarantine_entry_id: quarantine_entry.id,
+            is_success: false,
+            message: None,
+        };
+
+        match image_processing::compute_digest(&quarantine_entry.quarantine_path) {
+            Ok(digest) if digest != quarantine_entry.digest => {
+                result.message = Some("The quarantined file was modified".to_string());
+            }
+            Ok(_) => {
+                match file_operations::quarantine::restore_from_quarantine(
+                    Path::new(&quarantine_entry.quarantine_path),
+                    Path::new(&quarantine_entry.original_path),
+                ) {
+                    Ok(_) => {
+                        quarantine_entry_repository
+                            .delete(quarantine_entry.id)
+                            .await?;
+                        result.is_success = true;
+                    }
+                    Err(e) => {
+                        result.message = Some(format!("{e:#}"));
+                    }
+                }
+            }
+            Err(e) => {
+                result.message = Some(format!("{e:#}"));
+            }
+        }
+
+        results.push(result);
+    }
+
+    Ok(results)
+}
+
+// Permanently delete the quarantined files which are older than the given number of days
+#[server]
+pub async fn purge_quarantine_entries(
+    older_than_days: u32,
+) -> Result<Vec<QuarantineResult>, ServerFnError> {
+    let quarantine_entry_repository = get_container().await.quarantine_entry_repository.clone();
+    let created_before = chrono::Utc::now() - chrono::Duration::days(older_than_days.into());
+    let quarantine_entries = quarantine_entry_repository
+        .find_created_before(created_before)
+        .await?;
+
+    let mut results = Vec::new();
+    for quarantine_entry in quarantine_entries {
+        let mut result = QuarantineResult {
+            quarantine_entry_id: quarantine_entry.id,
+            is_success: false,
+            message: None,
+        };
+
+        let path = Path::new(&quarantine_entry.quarantine_path);
+        let removed = if path.exists() {
+            std::fs::remove_file(path)
+        } else {
+            Ok(())
+        };
+        match removed {
+            Ok(_) => {
+                quarantine_entry_repository
+                    .delete(quarantine_entry.id)
+                    .await?;
+                result.is_success = true;
+            }
+            Err(e) => {
+                result.message = Some(e.to_string());
+            }
+        }
+
+        results.push(result);
+    }
+
+    Ok(results)
+}
+
 #[server]
 pub async fn open_folder_in_explorer(path: String) -> Result<(), ServerFnError> {
     let path = Path::new(&path);
@@ -254,6 +1008,60 @@ pub async fn open_folder_in_explorer(path: String) -> Result<(), ServerFnError>
             .spawn()
             .map_err(|e| ServerFnError::new(e.to_string()))?;
     }
+    // dbus-send waits for the reply of the file manager, so it runs on a blocking thread
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        let path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || reveal_in_file_manager(&path))
+            .await?
+            .map_err(|e| ServerFnError::new(format!("{e:#}")))?;
+    }
+
+    Ok(())
+}
+
+// Select the file in the file manager through the FileManager1 D-Bus interface,
+// or open its parent directory with `xdg-open` when no file manager implements it
+#[cfg(all(feature = "server", unix, not(target_os = "macos")))]
+fn reveal_in_file_manager(path: &Path) -> Result<()> {
+    use std::{os::unix::ffi::OsStrExt, process::Command};
+
+    let path = path
+        .canonicalize()
+        .with_context(|| format!("File not found: {}", path.display()))?;
+
+    let mut uri = String::from("file://");
+    for &b in path.as_os_str().as_bytes() {
+        if b.is_ascii_alphanumeric() || b"/-_.~".contains(&b) {
+            uri.push(b as char);
+        } else {
+            uri.push_str(&format!("%{b:02X}"));
+        }
+    }
+
+    let dbus_error = match Command::new("dbus-send")
+        .args([
+            "--session",
+            "--print-reply",
+            "--dest=org.freedesktop.FileManager1",
+            "--type=method_call",
+            "/org/freedesktop/FileManager1",
+            "org.freedesktop.FileManager1.ShowItems",
+        ])
+        .arg(format!("array:string:{uri}"))
+        .arg("string:")
+        .output()
+    {
+        Ok(output) if output.status.success() => return Ok(()),
+        Ok(output) => String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        Err(e) => e.to_string(),
+    };
+
+    // xdg-open may only return when the file manager is closed, so it is not waited for
+    let parent = path.parent().unwrap_or(Path::new("/"));
+    if let Err(e) = Command::new("xdg-open").arg(parent).spawn() {
+        anyhow::bail!("Failed to open the file manager. D-Bus: {dbus_error}, xdg-open: {e}");
+    }
 
     Ok(())
 }
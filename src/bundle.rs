@@ -0,0 +1,482 @@
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{Read, Write},
+    path::Path,
+};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{image_processing, models};
+
+// Portable bundle of reference images
+// A bundle is a zip archive which contains a manifest encoded in JSON or CBOR
+// and optional JPEG thumbnails stored under `thumbnails/`
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+pub const BUNDLE_EXTENSION: &str = "zip";
+
+const JSON_MANIFEST: &str = "manifest.json";
+const CBOR_MANIFEST: &str = "manifest.cbor";
+const THUMBNAIL_SIZE: u32 = 256;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum BundleEncoding {
+    Json,
+    Cbor,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HasherConfig {
+    pub hash_version: i32,
+    pub algorithm: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl HasherConfig {
+    pub fn current() -> Self {
+        Self {
+            hash_version: image_processing::HASH_VERSION,
+            algorithm: image_processing::HASH_ALGORITHM.to_string(),
+            width: image_processing::HASH_WIDTH,
+            height: image_processing::HASH_HEIGHT,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub format_version: u32,
+    pub created_at: DateTime<Utc>,
+    pub hasher: HasherConfig,
+    pub entries: Vec<BundleEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleEntry {
+    pub original_filename: String,
+    pub original_path: String,
+    pub hash: Vec<u8>,
+    pub hash_version: i32,
+    pub digest: Option<String>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub collection: Option<String>,
+    // Path of the thumbnail inside the archive
+    pub thumbnail: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ImportReport {
+    pub imported: usize,
+    pub duplicates: usize,
+    pub conflicts: Vec<ImportConflict>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ImportConflict {
+    pub original_path: String,
+    pub reason: String,
+}
+
+// Build a bundle entry from a registered reference image
+// The thumbnail is only generated when the source file is still available
+pub fn entry_from_reference_image(
+    reference_image: &entity::reference_image::Model,
+    include_thumbnail: bool,
+) -> (BundleEntry, Option<Vec<u8>>) {
+    let path = Path::new(&reference_image.filepath);
+    let original_filename = path
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let thumbnail = if include_thumbnail && path.exists() {
+        image_processing::encode_thumbnail(&reference_image.filepath, THUMBNAIL_SIZE).ok()
+    } else {
+        None
+    };
+
+    let entry = BundleEntry {
+        original_filename,
+        original_path: reference_image.filepath.clone(),
+        hash: reference_image.hash.clone(),
+        hash_version: reference_image.hash_version,
+        digest: reference_image.digest.clone(),
+        width: reference_image.width,
+        height: reference_image.height,
+        collection: reference_image.collection.clone(),
+        thumbnail: thumbnail
+            .as_ref()
+            .map(|_| format!("thumbnails/{}.jpg", reference_image.id)),
+    };
+
+    (entry, thumbnail)
+}
+
+pub fn write_bundle(
+    path: &Path,
+    entries: Vec<(BundleEntry, Option<Vec<u8>>)>,
+    encoding: BundleEncoding,
+) -> Result<()> {
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create bundle: {}", path.display()))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default();
+
+    let mut manifest = BundleManifest {
+        format_version: BUNDLE_FORMAT_VERSION,
+        created_at: Utc::now(),
+        hasher: HasherConfig::current(),
+        entries: Vec::with_capacity(entries.len()),
+    };
+
+    for (entry, thumbnail) in entries {
+        if let (Some(name), Some(thumbnail)) = (&entry.thumbnail, thumbnail) {
+            zip.start_file(name.as_str(), options)?;
+            zip.write_all(&thumbnail)?;
+        }
+        manifest.entries.push(entry);
+    }
+
+    match encoding {
+        BundleEncoding::Json => {
+            zip.start_file(JSON_MANIFEST, options)?;
+            serde_json::to_writer_pretty(&mut zip, &manifest)
+                .context("Failed to encode manifest")?;
+        }
+        BundleEncoding::Cbor => {
+            zip.start_file(CBOR_MANIFEST, options)?;
+            ciborium::into_writer(&manifest, &mut zip).context("Failed to encode manifest")?;
+        }
+    }
+
+    zip.finish().context("Failed to write bundle")?;
+
+    Ok(())
+}
+
+pub fn read_bundle(path: &Path) -> Result<BundleManifest> {
+    let file =
+        File::open(path).with_context(|| format!("Failed to open bundle: {}", path.display()))?;
+    let mut zip = zip::ZipArchive::new(file).context("Failed to read bundle")?;
+
+    let manifest: BundleManifest = if zip.index_for_name(JSON_MANIFEST).is_some() {
+        let manifest = zip.by_name(JSON_MANIFEST)?;
+        serde_json::from_reader(manifest).context("Failed to decode manifest")?
+    } else if zip.index_for_name(CBOR_MANIFEST).is_some() {
+        let manifest = zip.by_name(CBOR_MANIFEST)?;
+        ciborium::from_reader(manifest).context("Failed to decode manifest")?
+    } else {
+        bail!("Bundle does not contain a manifest");
+    };
+
+    if manifest.format_version > BUNDLE_FORMAT_VERSION {
+        bail!(
+            "Bundle format version {} is newer than the supported version {}",
+            manifest.format_version,
+            BUNDLE_FORMAT_VERSION
+        );
+    }
+
+    Ok(manifest)
+}
+
+// Thumbnails of the entries which have one, by the content digest of their image
+// Entries without a digest are left out, as their thumbnail could not be matched to a file
+pub fn read_thumbnails(path: &Path, entries: &[BundleEntry]) -> Result<Vec<(String, Vec<u8>)>> {
+    let file =
+        File::open(path).with_context(|| format!("Failed to open bundle: {}", path.display()))?;
+    let mut zip = zip::ZipArchive::new(file).context("Failed to read bundle")?;
+
+    let mut thumbnails = Vec::new();
+    for entry in entries {
+        let (Some(name), Some(digest)) = (&entry.thumbnail, &entry.digest) else {
+            continue;
+        };
+        let mut thumbnail = Vec::new();
+        zip.by_name(name)
+            .with_context(|| format!("Bundle does not contain the thumbnail {name}"))?
+            .read_to_end(&mut thumbnail)
+            .with_context(|| format!("Failed to read the thumbnail {name}"))?;
+        thumbnails.push((digest.clone(), thumbnail));
+    }
+
+    Ok(thumbnails)
+}
+
+// Decide which bundle entries should be inserted into the local database
+// Entries are matched on their content digest, or on their hash when the digest is unknown
+pub fn plan_import(
+    manifest: BundleManifest,
+    existing_images: &[entity::reference_image::Model],
+) -> (
+    Vec<models::reference_image::ReferenceImageInput>,
+    ImportReport,
+) {
+    let mut known_digests = existing_images
+        .iter()
+        .filter_map(|i| i.digest.clone())
+        .collect::<HashSet<_>>();
+    let mut known_hashes = existing_images
+        .iter()
+        .map(|i| (i.hash.clone(), i.hash_version))
+        .collect::<HashSet<_>>();
+    let mut known_filepaths = existing_images
+        .iter()
+        .map(|i| i.filepath.clone())
+        .collect::<HashSet<_>>();
+
+    let mut report = ImportReport::default();
+    let mut new_images = Vec::new();
+
+    // Hashes of another hasher can not be compared with the local ones,
+    // even when the bundle claims the current hash version
+    let current_hasher = HasherConfig::current();
+    if manifest.hasher != current_hasher {
+        report.conflicts = manifest
+            .entries
+            .into_iter()
+            .map(|entry| ImportConflict {
+                original_path: entry.original_path,
+                reason: format!(
+                    "Hashed with {} {}x{} version {}, but the current hasher is {} {}x{} version {}",
+                    manifest.hasher.algorithm,
+                    manifest.hasher.width,
+                    manifest.hasher.height,
+                    manifest.hasher.hash_version,
+                    current_hasher.algorithm,
+                    current_hasher.width,
+                    current_hasher.height,
+                    current_hasher.hash_version
+                ),
+            })
+            .collect();
+        return (new_images, report);
+    }
+
+    for entry in manifest.entries {
+        let is_duplicate = match &entry.digest {
+            Some(digest) => known_digests.contains(digest),
+            None => known_hashes.contains(&(entry.hash.clone(), entry.hash_version)),
+        };
+        if is_duplicate {
+            report.duplicates += 1;
+            continue;
+        }
+
+        if !image_processing::is_current_hash_version(entry.hash_version) {
+            report.conflicts.push(ImportConflict {
+                original_path: entry.original_path,
+                reason: format!(
+                    "Hashed with hasher version {}, but the current version is {}",
+                    entry.hash_version,
+                    image_processing::HASH_VERSION
+                ),
+            });
+            continue;
+        }
+
+        if known_filepaths.contains(&entry.original_path) {
+            report.conflicts.push(ImportConflict {
+                original_path: entry.original_path,
+                reason: "A different reference image is already registered with this path"
+                    .to_string(),
+            });
+            continue;
+        }
+
+        if let Some(digest) = &entry.digest {
+            known_digests.insert(digest.clone());
+        }
+        known_hashes.insert((entry.hash.clone(), entry.hash_version));
+        known_filepaths.insert(entry.original_path.clone());
+
+        new_images.push(models::reference_image::ReferenceImageInput {
+            filepath: entry.original_path,
+            hash: entry.hash,
+            hash_version: entry.hash_version,
+            digest: entry.digest,
+            width: entry.width,
+            height: entry.height,
+            collection: entry.collection,
+        });
+    }
+    report.imported = new_images.len();
+
+    (new_images, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reference_image(
+        id: i32,
+        filepath: &str,
+        digest: Option<&str>,
+    ) -> entity::reference_image::Model {
+        entity::reference_image::Model {
+            id,
+            filepath: filepath.to_string(),
+            hash: vec![id as u8; 8],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            hash_version: image_processing::HASH_VERSION,
+            digest: digest.map(|d| d.to_string()),
+            width: Some(640),
+            height: Some(480),
+            collection: Some("test".to_string()),
+        }
+    }
+
+    fn manifest(entries: Vec<BundleEntry>) -> BundleManifest {
+        BundleManifest {
+            format_version: BUNDLE_FORMAT_VERSION,
+            created_at: Utc::now(),
+            hasher: HasherConfig::current(),
+            entries,
+        }
+    }
+
+    fn test_round_trip(encoding: BundleEncoding, name: &str) {
+        let dir = std::env::temp_dir().join(format!("imgdup-bundle-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+
+        let entries = vec![
+            entry_from_reference_image(
+                &reference_image(1, "/images/test_1.png", Some("digest_1")),
+                false,
+            ),
+            entry_from_reference_image(&reference_image(2, "/images/test_2.png", None), false),
+        ];
+        write_bundle(&path, entries, encoding).unwrap();
+
+        let manifest = read_bundle(&path).unwrap();
+        assert_eq!(manifest.format_version, BUNDLE_FORMAT_VERSION);
+        assert_eq!(manifest.hasher, HasherConfig::current());
+        assert_eq!(manifest.entries.len(), 2);
+        assert_eq!(manifest.entries[0].original_filename, "test_1.png");
+        assert_eq!(manifest.entries[0].original_path, "/images/test_1.png");
+        assert_eq!(manifest.entries[0].hash, vec![1; 8]);
+        assert_eq!(manifest.entries[0].digest, Some("digest_1".to_string()));
+        assert_eq!(manifest.entries[0].width, Some(640));
+        assert_eq!(manifest.entries[0].collection, Some("test".to_string()));
+        assert_eq!(manifest.entries[1].digest, None);
+        assert!(read_thumbnails(&path, &manifest.entries)
+            .unwrap()
+            .is_empty());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_round_trip_json() {
+        test_round_trip(BundleEncoding::Json, "json.zip");
+    }
+
+    #[test]
+    fn test_round_trip_cbor() {
+        test_round_trip(BundleEncoding::Cbor, "cbor.zip");
+    }
+
+    #[test]
+    fn test_round_trip_thumbnails() {
+        let dir =
+            std::env::temp_dir().join(format!("imgdup-bundle-thumbnails-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let image_path = dir.join("image.png");
+        image::RgbImage::new(64, 32).save(&image_path).unwrap();
+        let path = dir.join("thumbnails.zip");
+
+        let image_path = image_path.to_string_lossy().to_string();
+        let entries = vec![
+            entry_from_reference_image(&reference_image(1, &image_path, Some("digest_1")), true),
+            // Missing files are exported without a thumbnail
+            entry_from_reference_image(&reference_image(2, "/images/test_2.png", None), true),
+        ];
+        assert!(entries[0].1.is_some());
+        assert!(entries[1].1.is_none());
+        let thumbnail = entries[0].1.clone().unwrap();
+        write_bundle(&path, entries, BundleEncoding::Json).unwrap();
+
+        let manifest = read_bundle(&path).unwrap();
+        assert_eq!(
+            manifest.entries[0].thumbnail,
+            Some("thumbnails/1.jpg".to_string())
+        );
+        assert_eq!(manifest.entries[1].thumbnail, None);
+        assert_eq!(
+            read_thumbnails(&path, &manifest.entries).unwrap(),
+            vec![("digest_1".to_string(), thumbnail)]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_plan_import_rejects_other_hasher() {
+        let entry = entry_from_reference_image(
+            &reference_image(1, "/images/test_1.png", Some("digest_1")),
+            false,
+        )
+        .0;
+        let mut manifest = manifest(vec![entry]);
+        manifest.hasher.width += 1;
+
+        let (new_images, report) = plan_import(manifest, &[]);
+        assert!(new_images.is_empty());
+        assert_eq!(report.imported, 0);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].original_path, "/images/test_1.png");
+    }
+
+    #[test]
+    fn test_plan_import_skips_known_digests() {
+        let existing_images = vec![reference_image(1, "/images/test_1.png", Some("digest_1"))];
+        // Same content under another path, and the same hash with another content
+        let duplicate = BundleEntry {
+            original_path: "/other/test_1.png".to_string(),
+            ..entry_from_reference_image(&existing_images[0], false).0
+        };
+        let same_hash = BundleEntry {
+            original_path: "/other/test_2.png".to_string(),
+            digest: Some("digest_2".to_string()),
+            ..entry_from_reference_image(&existing_images[0], false).0
+        };
+
+        let (new_images, report) =
+            plan_import(manifest(vec![duplicate, same_hash]), &existing_images);
+        assert_eq!(report.duplicates, 1);
+        assert_eq!(report.imported, 1);
+        assert_eq!(new_images.len(), 1);
+        assert_eq!(new_images[0].filepath, "/other/test_2.png");
+    }
+
+    #[test]
+    fn test_plan_import_skips_known_hashes_without_digest() {
+        let existing_images = vec![reference_image(1, "/images/test_1.png", None)];
+        let duplicate = BundleEntry {
+            original_path: "/other/test_1.png".to_string(),
+            ..entry_from_reference_image(&existing_images[0], false).0
+        };
+        let new_entry = BundleEntry {
+            original_path: "/other/test_2.png".to_string(),
+            ..entry_from_reference_image(&reference_image(2, "/images/test_2.png", None), false).0
+        };
+        // Entries repeated inside the bundle are only imported once
+        let repeated = new_entry.clone();
+
+        let (new_images, report) = plan_import(
+            manifest(vec![duplicate, new_entry, repeated]),
+            &existing_images,
+        );
+        assert_eq!(report.duplicates, 2);
+        assert_eq!(report.imported, 1);
+        assert_eq!(new_images[0].filepath, "/other/test_2.png");
+        assert!(report.conflicts.is_empty());
+    }
+}
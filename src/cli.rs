@@ -0,0 +1,490 @@
+use std::{
+    collections::HashMap,
+    ffi::{OsStr, OsString},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use clap::{Args, Parser, Subcommand};
+
+use crate::{
+    bundle,
+    di::get_container,
+    models,
+    search_session::SearchSession,
+    services,
+    settings::{self, Overrides},
+};
+
+// Exit codes of the commands
+// `search` exits with `EXIT_MATCHES` when it found similar images, so it can be used in conditions
+pub const EXIT_SUCCESS: i32 = 0;
+pub const EXIT_MATCHES: i32 = 1;
+pub const EXIT_ERROR: i32 = 2;
+
+/// Find images similar to registered reference images.
+/// Without a command the desktop application is started.
+#[derive(Debug, Parser)]
+#[command(name = "imgdup", version)]
+pub struct Cli {
+    #[command(flatten)]
+    pub options: GlobalOptions,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Cli {
+    // Parse the command line of the process, and exit with the usage error otherwise
+    pub fn parse_args() -> Self {
+        Self::try_parse_args(std::env::args_os()).unwrap_or_else(|e| e.exit())
+    }
+
+    // Arguments the platform adds when it launches the application are left out,
+    // every other argument has to be valid
+    pub fn try_parse_args(args: impl IntoIterator<Item = OsString>) -> Result<Self, clap::Error> {
+        Self::try_parse_from(args.into_iter().filter(|arg| !is_platform_argument(arg)))
+    }
+}
+
+// macOS passes the process serial number when the application is opened from the Finder
+fn is_platform_argument(arg: &OsStr) -> bool {
+    arg.to_str().is_some_and(|arg| arg.starts_with("-psn_"))
+}
+
+#[derive(Debug, Args)]
+pub struct GlobalOptions {
+    /// Path of the config file
+    #[arg(long, global = true, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Path of the database file
+    #[arg(long, global = true, value_name = "FILE")]
+    database: Option<PathBuf>,
+
+    /// Minimum similarity in percent of the search results
+    #[arg(long, global = true, value_name = "PERCENT")]
+    similarity_threshold: Option<u32>,
+
+    /// Maximum number of search results
+    #[arg(long, global = true, value_name = "COUNT")]
+    result_limit: Option<usize>,
+}
+
+impl GlobalOptions {
+    // Command line options take precedence over environment variables
+    pub fn apply(&self, overrides: &mut Overrides) {
+        if let Some(config) = &self.config {
+            overrides.config_path = Some(config.clone());
+        }
+        if let Some(database) = &self.database {
+            overrides.database_path = Some(database.clone());
+        }
+        if let Some(similarity_threshold) = self.similarity_threshold {
+            overrides.similarity_threshold = Some(similarity_threshold);
+        }
+        if let Some(result_limit) = self.result_limit {
+            overrides.result_limit = Some(result_limit);
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Manage the reference images
+    Refs {
+        #[command(subcommand)]
+        command: RefsCommand,
+    },
+    /// Search directories for images similar to the reference images.
+    /// Exits with 1 when similar images were found and with 0 otherwise
+    Search {
+        #[arg(required = true)]
+        directories: Vec<String>,
+
+        /// Minimum similarity in percent
+        #[arg(long)]
+        threshold: Option<u32>,
+
+        /// Maximum number of results
+        #[arg(long)]
+        limit: Option<usize>,
+
+        /// Print the results as JSON, which can be passed to `delete --from-results`
+        #[arg(long)]
+        json: bool,
+    },
+    /// Delete the images of a search result.
+    /// Images which changed since the search are skipped,
+    /// and results exported from the UI only delete the selected images
+    Delete {
+        /// JSON file written by `search --json` or exported from the UI
+        #[arg(long, value_name = "FILE")]
+        from_results: PathBuf,
+
+        /// Move the images to the trash (default, only supported on Linux and other FreeDesktop systems)
+        #[arg(long, group = "method")]
+        trash: bool,
+
+        /// Move the images into the quarantine directory
+        #[arg(long, group = "method", value_name = "DIR")]
+        quarantine: Option<PathBuf>,
+
+        /// Delete the images permanently
+        #[arg(long, group = "method")]
+        permanent: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum RefsCommand {
+    /// Register images as reference images
+    Add {
+        #[arg(required = true)]
+        files: Vec<String>,
+
+        #[arg(long)]
+        collection: Option<String>,
+    },
+    /// List the reference images
+    List {
+        #[arg(long)]
+        json: bool,
+    },
+    /// Remove reference images by id
+    Rm {
+        #[arg(required = true)]
+        ids: Vec<i32>,
+    },
+    /// Export reference images into a bundle
+    Export {
+        /// Ids of the reference images
+        ids: Vec<i32>,
+
+        /// Export every reference image of the collection
+        #[arg(long)]
+        collection: Vec<String>,
+
+        /// Directory the bundle is written to
+        #[arg(long, value_name = "DIR", default_value = ".")]
+        output: PathBuf,
+
+        /// Encode the manifest as CBOR instead of JSON
+        #[arg(long)]
+        cbor: bool,
+
+        /// Do not include thumbnails of the images
+        #[arg(long)]
+        no_thumbnails: bool,
+    },
+    /// Import reference images from a bundle
+    Import { bundle: PathBuf },
+}
+
+// Run the command and return the exit code
+pub async fn run(command: Command) -> i32 {
+    let result = match command {
+        Command::Refs { command } => run_refs(command).await,
+        Command::Search {
+            directories,
+            threshold,
+            limit,
+            json,
+        } => search(directories, threshold, limit, json).await,
+        Command::Delete {
+            from_results,
+            quarantine,
+            permanent,
+            ..
+        } => {
+            let method = if quarantine.is_some() {
+                models::DeleteMethod::Quarantine
+            } else if permanent {
+                models::DeleteMethod::Permanent
+            } else {
+                models::DeleteMethod::Trash
+            };
+            if method.is_supported() {
+                delete(from_results, method, quarantine).await
+            } else {
+                Err(anyhow::anyhow!(
+                    "Moving files to the trash is not supported on this platform, \
+                     use --quarantine or --permanent"
+                ))
+            }
+        }
+    };
+
+    match result {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {e:#}");
+            EXIT_ERROR
+        }
+    }
+}
+
+async fn run_refs(command: RefsCommand) -> Result<i32> {
+    let container = get_container().await;
+    match command {
+        RefsCommand::Add { files, collection } => {
+            let files = files
+                .into_iter()
+                .map(|f| {
+                    std::fs::canonicalize(&f)
+                        .map(|p| p.to_string_lossy().to_string())
+                        .with_context(|| format!("File not found: {f}"))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let (summary, rejected) =
+                services::reference_images::register(container, files, collection, |_| {}).await?;
+            println!(
+                "Registered {} new and {} updated reference images",
+                summary.inserted.len(),
+                summary.updated.len()
+            );
+            if !rejected.is_empty() {
+                for rejected_file in rejected {
+                    eprintln!(
+                        "Skipped {}: {}",
+                        rejected_file.filepath, rejected_file.reason
+                    );
+                }
+                return Ok(EXIT_ERROR);
+            }
+        }
+        RefsCommand::List { json } => {
+            let reference_images = container.reference_image_repository.find_all().await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&reference_images)?);
+            } else {
+                for reference_image in reference_images {
+                    println!(
+                        "{}\t{}\t{}",
+                        reference_image.id,
+                        reference_image.collection.unwrap_or_default(),
+                        reference_image.filepath
+                    );
+                }
+            }
+        }
+        RefsCommand::Rm { ids } => {
+            let mut code = EXIT_SUCCESS;
+            for id in ids {
+                if container.reference_image_repository.delete(id).await? == 0 {
+                    eprintln!("Unknown reference image id: {id}");
+                    code = EXIT_ERROR;
+                }
+            }
+            return Ok(code);
+        }
+        RefsCommand::Export {
+            ids,
+            collection,
+            output,
+            cbor,
+            no_thumbnails,
+        } => {
+            let encoding = if cbor {
+                bundle::BundleEncoding::Cbor
+            } else {
+                bundle::BundleEncoding::Json
+            };
+            let bundle_path = services::reference_images::export_bundle(
+                container,
+                &ids,
+                &collection,
+                &output,
+                encoding,
+                !no_thumbnails,
+            )
+            .await?;
+            println!("{}", bundle_path.display());
+        }
+        RefsCommand::Import { bundle } => {
+            let report = services::reference_images::import_bundle(container, &bundle).await?;
+            println!(
+                "Imported: {}, duplicates skipped: {}, conflicts: {}",
+                report.imported,
+                report.duplicates,
+                report.conflicts.len()
+            );
+            for conflict in report.conflicts {
+                eprintln!("{}: {}", conflict.original_path, conflict.reason);
+            }
+        }
+    }
+
+    Ok(EXIT_SUCCESS)
+}
+
+async fn search(
+    directories: Vec<String>,
+    threshold: Option<u32>,
+    limit: Option<usize>,
+    json: bool,
+) -> Result<i32> {
+    let mut settings = settings::load()?;
+    if let Some(threshold) = threshold {
+        settings.similarity_threshold = threshold;
+    }
+    if let Some(limit) = limit {
+        settings.result_limit = limit;
+    }
+
+    let similar_images =
+        services::search::find_similar_images(get_container().await, &directories, &settings)
+            .await?;
+    let code = if similar_images.is_empty() {
+        EXIT_SUCCESS
+    } else {
+        EXIT_MATCHES
+    };
+
+    if json {
+        let report = models::SearchReport {
+            directories,
+            similar_images,
+            planned_deletions: None,
+        };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        for similar_image in similar_images {
+            println!("{}%\t{}", similar_image.similarity, similar_image.filepath);
+        }
+    }
+
+    Ok(code)
+}
+
+async fn delete(
+    from_results: PathBuf,
+    method: models::DeleteMethod,
+    quarantine_directory: Option<PathBuf>,
+) -> Result<i32> {
+    let content = std::fs::read_to_string(&from_results)
+        .with_context(|| format!("Failed to read {}", from_results.display()))?;
+    let report: models::SearchReport = serde_json::from_str(&content)
+        .with_context(|| format!("Invalid search result: {}", from_results.display()))?;
+
+    // Each image is checked against the state recorded in the search result
+    // and against the directory it was found in
+    let mut sessions = Vec::new();
+    for directory in &report.directories {
+        let matched_files = report
+            .similar_images
+            .iter()
+            .filter(|i| Path::new(&i.filepath).starts_with(directory))
+            .filter_map(|i| Some((i.filepath.clone(), i.fingerprint.clone()?)))
+            .collect::<HashMap<_, _>>();
+        sessions.push(SearchSession::new(Path::new(directory), matched_files)?);
+    }
+
+    let container = get_container().await;
+    let batch_id = services::deletion::new_batch_id();
+    let mut has_failure = false;
+    // Images which were already deleted or not selected in the UI are left alone
+    for similar_image in report
+        .similar_images
+        .iter()
+        .filter(|i| !i.is_deleted && report.is_planned_deletion(&i.filepath))
+    {
+        let path = Path::new(&similar_image.filepath);
+        let Some(session) = sessions
+            .iter()
+            .find(|s| s.contains(&similar_image.filepath))
+        else {
+            eprintln!(
+                "{}: The file is not part of the search result",
+                similar_image.filepath
+            );
+            has_failure = true;
+            continue;
+        };
+
+        let result = match session.verify(&similar_image.filepath) {
+            Ok(_) => {
+                services::deletion::delete_similar_image(
+                    container,
+                    &batch_id,
+                    path,
+                    method,
+                    quarantine_directory.as_deref(),
+                    session.root(),
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(_) => println!("{}", similar_image.filepath),
+            Err(e) => {
+                eprintln!("{}: {e:#}", similar_image.filepath);
+                has_failure = true;
+            }
+        }
+    }
+
+    if has_failure {
+        bail!("Failed to delete some images");
+    }
+
+    Ok(EXIT_SUCCESS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let cli = Cli::try_parse_from([
+            "imgdup",
+            "search",
+            "/images",
+            "/photos",
+            "--limit",
+            "5",
+            "--json",
+            "--database",
+            "/tmp/db.sqlite",
+        ])
+        .unwrap();
+        let mut overrides = Overrides::default();
+        cli.options.apply(&mut overrides);
+        assert_eq!(
+            overrides.database_path,
+            Some(PathBuf::from("/tmp/db.sqlite"))
+        );
+        assert!(matches!(
+            cli.command,
+            Some(Command::Search { directories, limit: Some(5), json: true, .. })
+                if directories == ["/images", "/photos"]
+        ));
+
+        assert!(Cli::try_parse_from(["imgdup"]).unwrap().command.is_none());
+        assert!(Cli::try_parse_from([
+            "imgdup",
+            "delete",
+            "--from-results",
+            "results.json",
+            "--trash",
+            "--permanent"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_try_parse_args() {
+        let args = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+
+        let cli = Cli::try_parse_args(args(&["imgdup", "-psn_0_12345"])).unwrap();
+        assert!(cli.command.is_none());
+
+        // Typos are usage errors, which exit with EXIT_ERROR
+        let error =
+            Cli::try_parse_args(args(&["imgdup", "delet", "--from-results", "x"])).unwrap_err();
+        assert_eq!(error.exit_code(), EXIT_ERROR);
+        assert!(Cli::try_parse_args(args(&["imgdup", "--unknown"])).is_err());
+    }
+}
@@ -1,58 +1,12 @@
 use dioxus::prelude::*;
 
-pub enum ToastType {
-    Info,
-    Success,
-    Warning,
-    Error,
-}
-
-pub async fn show_toast(message: &str, toast_type: ToastType) {
-    let (alert_class, icon) = match toast_type {
-        ToastType::Info => ("alert-info", "ℹ️"),
-        ToastType::Success => ("alert-success", "✅"),
-        ToastType::Warning => ("alert-warning", "⚠️"),
-        ToastType::Error => ("alert-error", "❌"),
-    };
-
-    let js = format!(
-        r#"
-    const alert = document.createElement('div');
-    alert.className = 'alert {alert_class}';
-    alert.style.transition = 'opacity 0.5s ease-in-out';
-
-    const icon = document.createElement('span');
-    icon.textContent = '{icon}';
-    icon.style.marginRight = '8px';
+use crate::utils;
 
-    const span = document.createElement('span');
-    span.textContent = '{message}';
+mod toast;
 
-    alert.appendChild(icon);
-    alert.appendChild(span);
-
-    let container = document.querySelector('.toast-container');
-    if (!container) {{
-        container = document.createElement('div');
-        container.className = 'toast-container toast toast-top toast-center';
-        container.style.zIndex = '9999';
-        document.body.appendChild(container);
-    }}
-
-    container.insertAdjacentElement('afterbegin', alert);
-    setTimeout(() => {{
-        alert.style.opacity = '0';
-        setTimeout(() => {{
-            alert.remove();
-            if (!container.hasChildNodes()) {{
-                container.remove();
-            }}
-        }}, 500);
-    }}, 2500);
-    "#,
-    );
-    let _ = dioxus::document::eval(js.as_str()).await;
-}
+pub use toast::{
+    show_toast, show_toast_with_action, NotificationHistory, ToastContainer, ToastType,
+};
 
 #[component]
 pub fn LoadingSpinner(message: String) -> Element {
@@ -71,6 +25,8 @@ pub fn ConfirmDialog(
     is_open: Signal<bool>,
     on_cancel: EventHandler<()>,
     on_confirm: EventHandler<()>,
+    #[props(default)] is_confirm_disabled: bool,
+    children: Element,
 ) -> Element {
     rsx! {
         dialog {
@@ -79,6 +35,7 @@ pub fn ConfirmDialog(
             div { class: "modal-box",
                 h3 { class: "font-bold text-lg", "{title}" }
                 div { class: "py-4", "{message}" }
+                {children}
                 div { class: "modal-action",
                     button {
                         class: "btn btn-ghost",
@@ -90,6 +47,7 @@ pub fn ConfirmDialog(
                     }
                     button {
                         class: "btn btn-warning",
+                        disabled: is_confirm_disabled,
                         onclick: move |_| {
                             on_confirm.call(());
                             is_open.set(false);
@@ -101,3 +59,23 @@ pub fn ConfirmDialog(
         }
     }
 }
+
+// Token of the thumbnail route, provided by `App`
+#[derive(Clone, Copy)]
+pub struct ThumbnailToken(pub Resource<Result<String, ServerFnError>>);
+
+// Small image of a local file, generated by the server in every build, desktop included
+// Originals are never loaded, as decoding large images freezes the webview
+#[component]
+pub fn Thumbnail(path: String, class: String) -> Element {
+    let src = match (use_context::<ThumbnailToken>().0)() {
+        Some(Ok(token)) => {
+            utils::path::thumbnail_url(&path, &token, utils::path::DEFAULT_THUMBNAIL_SIZE)
+        }
+        _ => String::new(),
+    };
+
+    rsx! {
+        img { class, src }
+    }
+}
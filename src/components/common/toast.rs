@@ -0,0 +1,198 @@
+use std::{
+    rc::Rc,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use dioxus::prelude::*;
+
+// Toasts are kept in global signals, so they can be shown from anywhere
+// and stay visible while the pages change
+static TOASTS: GlobalSignal<Vec<Toast>> = Signal::global(Vec::new);
+static TOAST_HISTORY: GlobalSignal<Vec<Toast>> = Signal::global(Vec::new);
+static NEXT_TOAST_ID: AtomicUsize = AtomicUsize::new(0);
+
+const TOAST_DURATION: Duration = Duration::from_millis(3000);
+const MAX_VISIBLE_TOASTS: usize = 5;
+const MAX_HISTORY_LENGTH: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToastType {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl ToastType {
+    fn alert_class(&self) -> &'static str {
+        match self {
+            ToastType::Info => "alert-info",
+            ToastType::Success => "alert-success",
+            ToastType::Warning => "alert-warning",
+            ToastType::Error => "alert-error",
+        }
+    }
+
+    fn icon(&self) -> &'static str {
+        match self {
+            ToastType::Info => "ℹ️",
+            ToastType::Success => "✅",
+            ToastType::Warning => "⚠️",
+            ToastType::Error => "❌",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ToastAction {
+    pub label: String,
+    pub on_click: Rc<dyn Fn()>,
+}
+
+#[derive(Clone)]
+pub struct Toast {
+    id: usize,
+    message: String,
+    toast_type: ToastType,
+    action: Option<ToastAction>,
+    created_at: chrono::DateTime<chrono::Local>,
+}
+
+impl PartialEq for Toast {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+pub fn show_toast(message: impl Into<String>, toast_type: ToastType) {
+    push_toast(message.into(), toast_type, None);
+}
+
+// Toast with a button, for example to undo what was just done
+pub fn show_toast_with_action(
+    message: impl Into<String>,
+    toast_type: ToastType,
+    label: impl Into<String>,
+    on_click: impl Fn() + 'static,
+) {
+    let action = ToastAction {
+        label: label.into(),
+        on_click: Rc::new(on_click),
+    };
+    push_toast(message.into(), toast_type, Some(action));
+}
+
+fn push_toast(message: String, toast_type: ToastType, action: Option<ToastAction>) {
+    let toast = Toast {
+        id: NEXT_TOAST_ID.fetch_add(1, Ordering::Relaxed),
+        message,
+        toast_type,
+        action,
+        created_at: chrono::Local::now(),
+    };
+
+    let mut history = TOAST_HISTORY.write();
+    history.push(toast.clone());
+    let overflow = history.len().saturating_sub(MAX_HISTORY_LENGTH);
+    history.drain(..overflow);
+
+    TOASTS.write().push(toast);
+}
+
+fn dismiss_toast(id: usize) {
+    TOASTS.write().retain(|t| t.id != id);
+}
+
+// Newest toasts are shown on top
+#[component]
+pub fn ToastContainer() -> Element {
+    let toasts = TOASTS();
+
+    rsx! {
+        div { class: "toast toast-top toast-center z-[9999]",
+            for toast in toasts.into_iter().rev().take(MAX_VISIBLE_TOASTS) {
+                ToastItem { key: "{toast.id}", toast }
+            }
+        }
+    }
+}
+
+// Errors stay until they are closed, the others disappear after a while
+#[component]
+fn ToastItem(toast: Toast) -> Element {
+    let id = toast.id;
+    let is_persistent = toast.toast_type == ToastType::Error;
+    use_future(move || async move {
+        if !is_persistent {
+            async_std::task::sleep(TOAST_DURATION).await;
+            dismiss_toast(id);
+        }
+    });
+
+    rsx! {
+        div { role: "alert", class: "alert {toast.toast_type.alert_class()} max-w-xl shadow",
+            span { "{toast.toast_type.icon()}" }
+            span { class: "break-all whitespace-pre-wrap", "{toast.message}" }
+            if let Some(action) = toast.action {
+                button {
+                    class: "btn btn-sm",
+                    onclick: {
+                        let on_click = action.on_click.clone();
+                        move |_| {
+                            on_click();
+                            dismiss_toast(id);
+                        }
+                    },
+                    "{action.label}"
+                }
+            }
+            button {
+                class: "btn btn-sm btn-ghost",
+                onclick: move |_| dismiss_toast(id),
+                "✕"
+            }
+        }
+    }
+}
+
+// Panel listing the recent toasts, newest first
+#[component]
+pub fn NotificationHistory() -> Element {
+    let mut is_open = use_signal(|| false);
+    let history = TOAST_HISTORY();
+
+    rsx! {
+        div { class: "relative",
+            button {
+                class: "btn btn-ghost btn-sm",
+                onclick: move |_| is_open.toggle(),
+                "Notifications ({history.len()})"
+            }
+            if is_open() {
+                div { class: "absolute right-0 z-50 w-96 max-h-96 overflow-y-auto bg-base-100 border rounded shadow p-2",
+                    div { class: "flex flex-row items-center justify-between mb-2",
+                        span { class: "text-sm font-bold", "Notifications" }
+                        button {
+                            class: "btn btn-xs btn-ghost",
+                            onclick: move |_| TOAST_HISTORY.write().clear(),
+                            "Clear"
+                        }
+                    }
+                    if history.is_empty() {
+                        p { class: "text-xs text-gray-500", "No notifications." }
+                    }
+                    for toast in history.into_iter().rev() {
+                        div { key: "{toast.id}", class: "flex flex-row gap-2 text-sm py-1 border-b",
+                            span { class: "text-xs text-gray-500 whitespace-nowrap",
+                                {toast.created_at.format("%H:%M:%S").to_string()}
+                            }
+                            span { "{toast.toast_type.icon()}" }
+                            span { class: "break-all", "{toast.message}" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
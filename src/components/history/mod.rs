@@ -0,0 +1,66 @@
+use crate::{backend, components::common};
+use dioxus::prelude::*;
+
+// Recent file operations from the action journal, newest first
+#[component]
+pub fn History() -> Element {
+    let mut history_resource = use_server_future(backend::get_action_history)?;
+
+    rsx! {
+        div { class: "container p-4",
+            div { class: "flex flex-row items-center gap-3 mb-4",
+                h3 { "History" }
+                button {
+                    class: "btn btn-sm btn-ghost",
+                    onclick: move |_| history_resource.restart(),
+                    "Reload"
+                }
+            }
+            match history_resource() {
+                Some(Ok(entries)) => rsx! {
+                    if entries.is_empty() {
+                        p { class: "text-xs text-gray-500", "No file operations yet." }
+                    } else {
+                        table { class: "table table-sm w-full",
+                            thead {
+                                tr {
+                                    th { "Time" }
+                                    th { "Operation" }
+                                    th { "Source" }
+                                    th { "Destination" }
+                                    th { "Batch" }
+                                }
+                            }
+                            tbody {
+                                for entry in entries {
+                                    tr { key: "{entry.id}",
+                                        td { class: "whitespace-nowrap",
+                                            {entry.created_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string()}
+                                        }
+                                        td {
+                                            "{entry.operation}"
+                                            if entry.reverts_batch_id.is_some() {
+                                                span { class: "badge badge-sm badge-ghost ml-1", "undo" }
+                                            }
+                                        }
+                                        td { class: "break-all", "{entry.source}" }
+                                        td { class: "break-all", "{entry.destination.clone().unwrap_or_default()}" }
+                                        td { class: "font-mono text-xs", title: "{entry.batch_id}",
+                                            {entry.batch_id.chars().take(8).collect::<String>()}
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                },
+                Some(Err(e)) => rsx! {
+                    div { role: "alert", class: "alert alert-error", span { "{e}" } }
+                },
+                None => rsx! {
+                    common::LoadingSpinner { message: "Loading history..." }
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use std::time::Duration;
+
+use dioxus::prelude::*;
+
+use crate::{
+    backend,
+    components::common,
+    models::{self, ImageDetails},
+    utils,
+};
+
+// Largest size served by the thumbnail route
+const COMPARISON_SIZE: u32 = 512;
+const MAX_ZOOM: f64 = 16.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ComparisonMode {
+    SideBySide,
+    Swipe,
+    Blink,
+}
+
+// Modal comparing a search result with its matched reference
+#[component]
+pub fn Comparison(compared_image: Signal<Option<models::SimilarImage>>) -> Element {
+    rsx! {
+        if let Some(similar_image) = compared_image() {
+            ComparisonModal { key: "{similar_image.filepath}", similar_image, compared_image }
+        }
+    }
+}
+
+#[component]
+fn ComparisonModal(
+    similar_image: models::SimilarImage,
+    compared_image: Signal<Option<models::SimilarImage>>,
+) -> Element {
+    let mut mode = use_signal(|| ComparisonMode::SideBySide);
+    // Zoom and pan are shared by both images
+    let mut zoom = use_signal(|| 1.0f64);
+    let mut offset = use_signal(|| (0.0f64, 0.0f64));
+    let mut swipe_position = use_signal(|| 50u32);
+    let mut is_reference_shown = use_signal(|| false);
+    let mut is_difference_shown = use_signal(|| false);
+
+    let filepath = similar_image.filepath.clone();
+    let matched_reference = similar_image.matched_reference.clone();
+    let comparison = use_resource(move || {
+        let filepath = filepath.clone();
+        let matched_reference = matched_reference.clone();
+        async move { backend::compare_images(filepath, matched_reference).await }
+    });
+
+    use_future(move || async move {
+        loop {
+            async_std::task::sleep(Duration::from_millis(500)).await;
+            if *mode.peek() == ComparisonMode::Blink {
+                is_reference_shown.toggle();
+            }
+        }
+    });
+
+    let token = match (use_context::<common::ThumbnailToken>().0)() {
+        Some(Ok(token)) => token,
+        _ => String::new(),
+    };
+    let candidate_src =
+        utils::path::thumbnail_url(&similar_image.filepath, &token, COMPARISON_SIZE);
+    let reference_src = similar_image
+        .matched_reference
+        .as_ref()
+        .map(|r| utils::path::thumbnail_url(r, &token, COMPARISON_SIZE));
+    let difference_src = similar_image
+        .matched_reference
+        .as_ref()
+        .filter(|_| is_difference_shown())
+        .map(|r| utils::path::difference_url(&similar_image.filepath, r, &token, COMPARISON_SIZE));
+
+    rsx! {
+        dialog {
+            id: "comparison_modal",
+            class: "modal modal-open",
+            div { class: "modal-box max-w-5xl",
+                div { class: "container p-4",
+                    h3 { class: "mb-4", "Compare with the matched reference" }
+                    if let Some(reference_src) = reference_src {
+                        div { class: "flex flex-row items-center gap-3 mb-4",
+                            div { class: "join",
+                                for (value, label) in [(ComparisonMode::SideBySide, "Side by side"), (ComparisonMode::Swipe, "Swipe"), (ComparisonMode::Blink, "Blink")] {
+                                    button {
+                                        class: "btn btn-sm join-item",
+                                        class: if mode() == value { "btn-active" },
+                                        onclick: move |_| mode.set(value),
+                                        "{label}"
+                                    }
+                                }
+                            }
+                            label { class: "label text-sm",
+                                input {
+                                    r#type: "checkbox",
+                                    class: "checkbox checkbox-sm",
+                                    checked: is_difference_shown(),
+                                    onchange: move |e| is_difference_shown.set(e.checked()),
+                                }
+                                "Difference"
+                            }
+                            button {
+                                class: "btn btn-sm btn-ghost",
+                                onclick: move |_| {
+                                    zoom.set(1.0);
+                                    offset.set((0.0, 0.0));
+                                },
+                                "Reset zoom"
+                            }
+                        }
+                        {match mode() {
+                            ComparisonMode::SideBySide => rsx! {
+                                div { class: "grid grid-cols-2 gap-2",
+                                    Viewport { zoom, offset,
+                                        ViewportImage { src: candidate_src }
+                                        if let Some(difference_src) = difference_src {
+                                            ViewportImage { src: difference_src, class: "opacity-80" }
+                                        }
+                                    }
+                                    Viewport { zoom, offset,
+                                        ViewportImage { src: reference_src }
+                                    }
+                                }
+                            },
+                            ComparisonMode::Swipe => rsx! {
+                                Viewport { zoom, offset,
+                                    ViewportImage { src: candidate_src }
+                                    ViewportImage { src: reference_src, style: "clip-path: inset(0 0 0 {swipe_position}%)" }
+                                    if let Some(difference_src) = difference_src {
+                                        ViewportImage { src: difference_src, class: "opacity-80" }
+                                    }
+                                }
+                                input {
+                                    r#type: "range",
+                                    class: "range range-xs w-full mt-2",
+                                    min: "0",
+                                    max: "100",
+                                    value: "{swipe_position}",
+                                    oninput: move |e| {
+                                        if let Ok(position) = e.value().parse() {
+                                            swipe_position.set(position);
+                                        }
+                                    },
+                                }
+                                div { class: "flex flex-row justify-between text-xs text-gray-500",
+                                    span { "Candidate" }
+                                    span { "Reference" }
+                                }
+                            },
+                            ComparisonMode::Blink => rsx! {
+                                Viewport { zoom, offset,
+                                    ViewportImage { src: if is_reference_shown() { reference_src } else { candidate_src } }
+                                    if let Some(difference_src) = difference_src {
+                                        ViewportImage { src: difference_src, class: "opacity-80" }
+                                    }
+                                }
+                                p { class: "text-xs text-gray-500 mt-2",
+                                    if is_reference_shown() { "Reference" } else { "Candidate" }
+                                }
+                            },
+                        }}
+                    } else {
+                        Viewport { zoom, offset,
+                            ViewportImage { src: candidate_src }
+                        }
+                    }
+
+                    {match comparison() {
+                        Some(Ok(comparison)) => rsx! {
+                            table { class: "table table-sm w-full mt-4",
+                                thead {
+                                    tr {
+                                        th {}
+                                        th { "Candidate" }
+                                        th { "Reference" }
+                                    }
+                                }
+                                tbody {
+                                    for (label, candidate, reference) in detail_rows(&comparison) {
+                                        tr {
+                                            th { "{label}" }
+                                            td { class: "break-all", "{candidate}" }
+                                            td { class: "break-all", "{reference}" }
+                                        }
+                                    }
+                                }
+                            }
+                            if let Some(hash_distance) = comparison.hash_distance {
+                                p { class: "text-sm mt-2", "Hash distance: {hash_distance} bits ({similar_image.similarity}% similar)" }
+                            }
+                        },
+                        Some(Err(e)) => rsx! {
+                            div { class: "text-red-500 text-sm mt-4", "{e}" }
+                        },
+                        None => rsx! {
+                            common::LoadingSpinner { message: "Loading image details..." }
+                        },
+                    }}
+                }
+            }
+            form { method: "dialog", class: "modal-backdrop", button { onclick: move |_| compared_image.set(None), "close" } }
+        }
+    }
+}
+
+// Area showing the images with the shared zoom and pan
+// Scrolling zooms and dragging pans
+#[component]
+fn Viewport(zoom: Signal<f64>, offset: Signal<(f64, f64)>, children: Element) -> Element {
+    let mut drag_position = use_signal(|| None::<(f64, f64)>);
+
+    rsx! {
+        div { class: "relative overflow-hidden h-96 bg-base-200 cursor-grab select-none",
+            onwheel: move |e| {
+                e.prevent_default();
+                let factor = if e.delta().strip_units().y < 0.0 { 1.25 } else { 0.8 };
+                zoom.set((zoom() * factor).clamp(1.0, MAX_ZOOM));
+                if zoom() == 1.0 {
+                    offset.set((0.0, 0.0));
+                }
+            },
+            onmousedown: move |e| {
+                let position = e.client_coordinates();
+                drag_position.set(Some((position.x, position.y)));
+            },
+            onmousemove: move |e| {
+                if let Some((x, y)) = drag_position() {
+                    let position = e.client_coordinates();
+                    let (offset_x, offset_y) = offset();
+                    offset.set((offset_x + position.x - x, offset_y + position.y - y));
+                    drag_position.set(Some((position.x, position.y)));
+                }
+            },
+            onmouseup: move |_| drag_position.set(None),
+            onmouseleave: move |_| drag_position.set(None),
+            div {
+                class: "absolute inset-0",
+                style: "transform: translate({offset().0}px, {offset().1}px) scale({zoom()})",
+                {children}
+            }
+        }
+    }
+}
+
+#[component]
+fn ViewportImage(
+    src: String,
+    #[props(default)] class: String,
+    #[props(default)] style: String,
+) -> Element {
+    rsx! {
+        img {
+            class: "absolute inset-0 w-full h-full object-contain pointer-events-none {class}",
+            style,
+            src,
+        }
+    }
+}
+
+type DetailField = fn(&ImageDetails) -> Option<String>;
+
+// Rows of the metadata table with the values of the candidate and the reference
+fn detail_rows(comparison: &models::ImageComparison) -> Vec<(&'static str, String, String)> {
+    let fields: [(&'static str, DetailField); 6] = [
+        ("Path", |d| Some(d.filepath.clone())),
+        ("Dimensions", |d| {
+            Some(format!("{}x{}", d.width?, d.height?))
+        }),
+        ("File size", |d| d.size.map(|size| format!("{size} bytes"))),
+        ("Format", |d| d.format.clone()),
+        ("Taken", |d| d.taken_at.clone()),
+        ("Camera", |d| d.camera.clone()),
+    ];
+    let describe = |details: Option<&ImageDetails>, field: DetailField| {
+        details.and_then(field).unwrap_or_else(|| "-".to_string())
+    };
+    fields
+        .into_iter()
+        .map(|(label, field)| {
+            (
+                label,
+                describe(Some(&comparison.candidate), field),
+                describe(comparison.reference.as_ref(), field),
+            )
+        })
+        .collect()
+}
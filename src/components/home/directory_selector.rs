@@ -1,10 +1,34 @@
-use dioxus::prelude::*;
+use dioxus::{html::HasFileData, prelude::*};
 use dioxus_free_icons::{icons, Icon};
 
+use crate::{backend, components::common};
+
 #[component]
 pub fn DirectorySelector(selected_directory: Signal<String>) -> Element {
+    let mut is_dragging = use_signal(|| false);
+
     rsx! {
-        div { class: "flex flex-row items-center pb-4",
+        div { class: "flex flex-row items-center pb-4 rounded",
+            class: if is_dragging() { "outline-dashed outline-2 outline-primary" },
+            ondragover: move |e| {
+                e.prevent_default();
+                is_dragging.set(true);
+            },
+            ondragleave: move |_| is_dragging.set(false),
+            // A dropped folder becomes the search root
+            ondrop: move |e| async move {
+                e.prevent_default();
+                is_dragging.set(false);
+                let files = e.files().map(|file_engine| file_engine.files()).unwrap_or_default();
+                let [directory] = files.as_slice() else {
+                    common::show_toast("Please drop a single folder", common::ToastType::Info);
+                    return;
+                };
+                match backend::check_search_directory(directory.clone()).await {
+                    Ok(()) => selected_directory.set(directory.clone()),
+                    Err(e) => common::show_toast(e.to_string(), common::ToastType::Warning),
+                }
+            },
                 input {
                     r#type: "file",
                     id: "custom-input",
@@ -14,7 +38,7 @@ pub fn DirectorySelector(selected_directory: Signal<String>) -> Element {
                     onchange: move |e| {
                         if let Some(file_engine) = e.files() {
                             let files = file_engine.files();
-                            if files.len() > 0{
+                            if !files.is_empty() {
                                 selected_directory.set(files[0].to_string());
                             }
                         }
@@ -23,10 +47,10 @@ pub fn DirectorySelector(selected_directory: Signal<String>) -> Element {
                 label {
                     r#for: "custom-input",
                     class: "btn btn-outline btn-primary mr-3",
-                    "Select directory"
+                    "Select or drop directory"
                 }
                 div { class: "text-sm text-slate-500",
-                    if selected_directory() == "" {
+                    if selected_directory().is_empty() {
                         div { "No directory selected" }
                     } else {
                         div { class: "inline-block p-1 group rounded hover:bg-slate-100 transition-colors duration-100",
@@ -0,0 +1,93 @@
+use std::collections::{BTreeMap, HashSet};
+
+use dioxus::prelude::*;
+
+use crate::{backend, components::common, models, report::ReportFormat};
+
+// Export of the current results, the selected images are written as the deletion plan
+#[component]
+pub fn ExportResults(
+    selected_directory: Signal<String>,
+    similar_images: Signal<BTreeMap<u32, models::SimilarImage>>,
+    selected_images: Signal<HashSet<u32>>,
+) -> Element {
+    let mut report_format = use_signal(|| ReportFormat::Html);
+    let mut output_directory = use_signal(String::new);
+    let mut is_exporting = use_signal(|| false);
+
+    rsx! {
+        div { class: "container p-4",
+            h3 { class: "mb-4", "Export results" }
+            div { class: "flex flex-row items-center gap-3 pb-2",
+                select {
+                    class: "select select-sm w-32",
+                    onchange: move |e| {
+                        if let Some(value) = e.value().parse::<usize>().ok().and_then(|i| ReportFormat::ALL.get(i)) {
+                            report_format.set(*value);
+                        }
+                    },
+                    for (i, value) in ReportFormat::ALL.into_iter().enumerate() {
+                        option { value: "{i}", selected: report_format() == value, "{value}" }
+                    }
+                }
+                input {
+                    r#type: "file",
+                    id: "export-output-directory-input",
+                    multiple: false,
+                    directory: true,
+                    hidden: true,
+                    onchange: move |e| {
+                        if let Some(file_engine) = e.files() {
+                            let files = file_engine.files();
+                            if !files.is_empty() {
+                                output_directory.set(files[0].to_string());
+                            }
+                        }
+                    },
+                }
+                label {
+                    r#for: "export-output-directory-input",
+                    class: "btn btn-outline btn-primary btn-sm",
+                    "Select output directory"
+                }
+                div { class: "text-sm text-slate-500 break-all",
+                    if output_directory().is_empty() {
+                        "No directory selected"
+                    } else {
+                        "{output_directory()}"
+                    }
+                }
+            }
+            button {
+                class: "btn btn-outline w-full",
+                disabled: is_exporting() || output_directory().is_empty() || similar_images().is_empty(),
+                onclick: move |_| async move {
+                    is_exporting.set(true);
+
+                    let similar_images = similar_images();
+                    let report = models::SearchReport {
+                        directories: vec![selected_directory()],
+                        planned_deletions: Some(
+                            selected_images()
+                                .iter()
+                                .filter_map(|image_id| Some(similar_images.get(image_id)?.filepath.clone()))
+                                .collect(),
+                        ),
+                        similar_images: similar_images.into_values().collect(),
+                    };
+                    match backend::export_search_results(report, report_format(), output_directory()).await {
+                        Ok(path) => {
+                            common::show_toast(format!("Exported to {path}"), common::ToastType::Success);
+                        }
+                        Err(e) => {
+                            common::show_toast(e.to_string(), common::ToastType::Error);
+                        }
+                    }
+
+                    is_exporting.set(false);
+                },
+                "Export"
+            }
+        }
+    }
+}
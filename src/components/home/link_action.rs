@@ -0,0 +1,147 @@
+use std::collections::{BTreeMap, HashSet};
+
+use dioxus::prelude::*;
+use futures::StreamExt;
+
+use crate::{backend, components::common, models};
+
+#[component]
+pub fn LinkAction(
+    similar_images: Signal<BTreeMap<u32, models::SimilarImage>>,
+    selected_images: Signal<HashSet<u32>>,
+) -> Element {
+    let mut link_method = use_signal(models::LinkMethod::default);
+    let mut link_target = use_signal(models::LinkTarget::default);
+    let mut is_confirm_dialog_open = use_signal(|| false);
+    let mut allow_different_content = use_signal(|| false);
+
+    rsx! {
+        div { class: "container p-4",
+            div { class: "flex flex-row items-center gap-4 pb-4",
+                for method in [models::LinkMethod::Hardlink, models::LinkMethod::Symlink, models::LinkMethod::Reflink] {
+                    label { class: "label text-sm",
+                        input {
+                            r#type: "radio",
+                            name: "link-method",
+                            class: "radio",
+                            checked: link_method() == method,
+                            onchange: move |_| link_method.set(method),
+                        }
+                        "{method}"
+                    }
+                }
+                select {
+                    class: "select select-sm w-48",
+                    onchange: move |e| {
+                        link_target.set(if e.value() == "keeper" { models::LinkTarget::Keeper } else { models::LinkTarget::Reference });
+                    },
+                    option { value: "reference", selected: link_target() == models::LinkTarget::Reference, "{models::LinkTarget::Reference}" }
+                    option { value: "keeper", selected: link_target() == models::LinkTarget::Keeper, "{models::LinkTarget::Keeper}" }
+                }
+                label { class: "label text-sm",
+                    input {
+                        r#type: "checkbox",
+                        class: "checkbox checkbox-sm checkbox-error",
+                        checked: allow_different_content(),
+                        onchange: move |e| allow_different_content.set(e.checked()),
+                    }
+                    "Link images with different contents"
+                }
+            }
+            button {
+                class: "btn btn-outline btn-warning w-full",
+                disabled: selected_images().is_empty(),
+                onclick: move |_| is_confirm_dialog_open.set(true),
+                "Link selected images to original"
+            }
+        }
+
+        common::ConfirmDialog {
+            title: "Are you sure you want to replace the selected images with links?".to_string(),
+            message: if allow_different_content() {
+                format!(
+                    "Selected {} images will be replaced with a {} to the {}, even when their contents differ. The current content of the files can not be restored.",
+                    selected_images().len(),
+                    link_method().to_string().to_lowercase(),
+                    link_target().to_string().to_lowercase(),
+                )
+            } else {
+                format!(
+                    "Selected {} images will be replaced with a {} to the {}. Images whose contents differ from the {} are skipped.",
+                    selected_images().len(),
+                    link_method().to_string().to_lowercase(),
+                    link_target().to_string().to_lowercase(),
+                    link_target().to_string().to_lowercase(),
+                )
+            },
+            is_open: is_confirm_dialog_open,
+            on_confirm: move |_| async move {
+                let mut targeted_images = Vec::new();
+                let mut all_success = true;
+                for image_id in selected_images() {
+                    match find_link_target(&similar_images(), &selected_images(), image_id, link_target()) {
+                        Some(target) => {
+                            let filepath = similar_images().get(&image_id).unwrap().filepath.clone();
+                            targeted_images.push((image_id, filepath, target));
+                        }
+                        None => {
+                            if let Some(similar_image) = similar_images.write().get_mut(&image_id) {
+                                similar_image.error_message = Some("No image to link to".to_string());
+                            }
+                            all_success = false;
+                        }
+                    }
+                }
+
+                if let Ok(stream) = backend::link_similar_images_stream(targeted_images, link_method(), allow_different_content()).await {
+                    let mut stream = stream.into_inner();
+                    while let Some(Ok(progress)) = stream.next().await {
+                        let mut similar_images = similar_images.write();
+                        let similar_image = similar_images.get_mut(&progress.image_id).unwrap();
+                        if progress.is_success {
+                            // The duplicate is gone even though its path is still there
+                            similar_image.is_deleted = true;
+                        } else {
+                            let message = progress.message.unwrap_or("Unknown error".to_string());
+                            similar_image.error_message = Some(message);
+
+                            all_success = false;
+                        }
+                    }
+                }
+
+                if all_success {
+                    common::show_toast("All selected images were replaced with links", common::ToastType::Success);
+                } else {
+                    common::show_toast("Failed to link some selected images", common::ToastType::Error);
+                }
+
+                selected_images.write().clear();
+                is_confirm_dialog_open.set(false);
+            },
+            on_cancel: move |_| async move {},
+        }
+    }
+}
+
+// The keeper is the unselected image with the highest similarity which matched the same reference
+fn find_link_target(
+    similar_images: &BTreeMap<u32, models::SimilarImage>,
+    selected_images: &HashSet<u32>,
+    image_id: u32,
+    link_target: models::LinkTarget,
+) -> Option<String> {
+    let matched_reference = similar_images.get(&image_id)?.matched_reference.clone()?;
+    match link_target {
+        models::LinkTarget::Reference => Some(matched_reference),
+        models::LinkTarget::Keeper => similar_images
+            .iter()
+            .filter(|(id, i)| {
+                !selected_images.contains(id)
+                    && !i.is_deleted
+                    && i.matched_reference.as_ref() == Some(&matched_reference)
+            })
+            .max_by_key(|(_, i)| i.similarity)
+            .map(|(_, i)| i.filepath.clone()),
+    }
+}
@@ -1,116 +1,431 @@
 use crate::{
     backend,
-    components::{common, reference_images},
+    components::common,
     models::{self},
+    result_filter::{ResultFilter, SortKey},
 };
 use std::collections::{BTreeMap, HashSet};
 
-use dioxus::prelude::*;
+use dioxus::{logger::tracing::warn, prelude::*};
 use futures::StreamExt;
 use search_result::SearchResult;
 
+mod comparison;
 mod directory_selector;
+mod export;
+mod link_action;
 mod search_result;
+mod selection;
+
+// State of the search page which is kept while other pages are shown
+#[derive(Clone, Copy)]
+pub struct SearchState {
+    pub selected_directory: Signal<String>,
+    pub similar_images: Signal<BTreeMap<u32, models::SimilarImage>>,
+    pub selected_images: Signal<HashSet<u32>>,
+    pub is_searching: Signal<bool>,
+    pub filter: Signal<ResultFilter>,
+    pub sort_key: Signal<SortKey>,
+    pub is_descending: Signal<bool>,
+    pub marked_images: Signal<HashSet<u32>>,
+    pub is_deleting: Signal<bool>,
+    // Processed and total images of the running deletion
+    pub delete_progress: Signal<(usize, usize)>,
+}
+
+impl Default for SearchState {
+    fn default() -> Self {
+        Self {
+            selected_directory: Signal::new(String::new()),
+            similar_images: Signal::new(BTreeMap::new()),
+            selected_images: Signal::new(HashSet::new()),
+            is_searching: Signal::new(false),
+            filter: Signal::new(ResultFilter::default()),
+            sort_key: Signal::new(SortKey::Similarity),
+            is_descending: Signal::new(true),
+            marked_images: Signal::new(HashSet::new()),
+            is_deleting: Signal::new(false),
+            delete_progress: Signal::new((0, 0)),
+        }
+    }
+}
+
+// Failures listed in the toast after undoing a batch
+const MAX_LISTED_FAILURES: usize = 3;
 
 #[component]
 pub fn Home() -> Element {
-    let selected_directory = use_signal(String::new);
-    let mut selected_images = use_signal(HashSet::<u32>::new);
-
-    let mut similar_images = use_signal(|| BTreeMap::<u32, models::SimilarImage>::new());
-    let mut is_searching = use_signal(|| false);
+    let SearchState {
+        selected_directory,
+        mut similar_images,
+        mut selected_images,
+        mut is_searching,
+        mut marked_images,
+        mut is_deleting,
+        mut delete_progress,
+        ..
+    } = use_context();
 
+    let mut delete_method = use_signal(models::DeleteMethod::default);
+    let mut quarantine_directory = use_signal(String::new);
     let mut is_confirm_dialog_open = use_signal(|| false);
+    let mut dry_run_reports = use_signal(Vec::<models::DryRunReport>::new);
+    let mut is_dry_running = use_signal(|| false);
+    // Images checked by the dry run, either the selection or the marked images
+    let mut delete_candidates = use_signal(HashSet::<u32>::new);
+
+    // The delete method and quarantine directory start from the saved defaults
+    let settings_resource = use_server_future(backend::get_settings)?;
+    use_effect(move || {
+        if let Some(Ok(info)) = settings_resource() {
+            // A settings file from another machine may name a method which is not supported here
+            if info.delete_methods.contains(&info.settings.delete_method) {
+                delete_method.set(info.settings.delete_method);
+            } else {
+                delete_method.set(models::DeleteMethod::Permanent);
+            }
+            quarantine_directory.set(info.settings.quarantine_directory.unwrap_or_default());
+        }
+    });
+
+    // Check the selected images with a dry run, then ask for confirmation
+    let start_delete = move |image_ids: HashSet<u32>| async move {
+        if is_dry_running() || is_deleting() {
+            return;
+        }
+        if image_ids.is_empty() {
+            common::show_toast("Please select images to delete", common::ToastType::Info);
+            return;
+        }
+        if delete_method() == models::DeleteMethod::Quarantine && quarantine_directory().is_empty()
+        {
+            common::show_toast(
+                "Please select a quarantine directory",
+                common::ToastType::Info,
+            );
+            return;
+        }
+
+        is_dry_running.set(true);
+
+        // A search running in the background may have replaced the results
+        let selected_similar_images = image_ids
+            .iter()
+            .filter_map(|image_id| match similar_images().get(image_id) {
+                Some(similar_image) => Some((*image_id, similar_image.filepath.clone())),
+                None => {
+                    warn!("Skipped image {image_id}, which is no longer in the search result");
+                    None
+                }
+            })
+            .collect::<Vec<(u32, String)>>();
+
+        match backend::dry_run_delete_similar_images(
+            selected_similar_images,
+            delete_method(),
+            Some(quarantine_directory()).filter(|d| !d.is_empty()),
+        )
+        .await
+        {
+            Ok(reports) => {
+                dry_run_reports.set(reports);
+                delete_candidates.set(image_ids);
+                is_confirm_dialog_open.set(true);
+            }
+            Err(e) => {
+                common::show_toast(e.to_string(), common::ToastType::Error);
+            }
+        }
+
+        is_dry_running.set(false);
+    };
+
+    // Shared by the undo button, which undoes the last batch,
+    // and the toast shown after deleting, which undoes the batch it is about
+    let undo = move |batch_id: Option<String>| async move {
+        let result = match batch_id {
+            Some(batch_id) => backend::undo_batch(batch_id).await,
+            None => backend::undo_last_batch().await,
+        };
+        match result {
+            Ok(report) => {
+                if report.batch_id.is_none() {
+                    common::show_toast("Nothing to undo", common::ToastType::Info);
+                    return;
+                }
+
+                for similar_image in similar_images.write().values_mut() {
+                    if report.restored.contains(&similar_image.filepath) {
+                        similar_image.is_deleted = false;
+                        similar_image.error_message = None;
+                    }
+                }
+
+                if report.failures.is_empty() {
+                    common::show_toast(
+                        format!("Restored {} images", report.restored.len()),
+                        common::ToastType::Success,
+                    );
+                } else {
+                    // One toast for the whole batch, listing the first failures
+                    let mut message = format!(
+                        "Restored {} images, failed to restore {} images:",
+                        report.restored.len(),
+                        report.failures.len()
+                    );
+                    for failure in report.failures.iter().take(MAX_LISTED_FAILURES) {
+                        message.push_str(&format!("\n{}: {}", failure.source, failure.reason));
+                    }
+                    if report.failures.len() > MAX_LISTED_FAILURES {
+                        message.push_str(&format!(
+                            "\nand {} more",
+                            report.failures.len() - MAX_LISTED_FAILURES
+                        ));
+                    }
+                    common::show_toast(message, common::ToastType::Error);
+                }
+                if let Some(journal_error) = report.journal_error {
+                    common::show_toast(journal_error, common::ToastType::Error);
+                }
+            }
+            Err(e) => {
+                common::show_toast(e.to_string(), common::ToastType::Error);
+            }
+        }
+    };
 
     rsx! {
         div { class: "container p-4",
-            div { class: "pb-4",
-                reference_images::ReferenceImages {  }
-            }
             directory_selector::DirectorySelector { selected_directory }
 
             button {
                 class: "btn btn-primary w-full",
                 disabled: selected_directory().is_empty() || is_searching(),
-                onclick: move |_| async move {
-                    if selected_directory().is_empty() {
-                        common::show_toast("Please select a directory", common::ToastType::Info).await;
-                        return;
-                    }
+                onclick: move |_| {
+                    // The search keeps running when another page is opened
+                    spawn_forever(async move {
+                        if selected_directory().is_empty() {
+                            common::show_toast("Please select a directory", common::ToastType::Info);
+                            return;
+                        }
 
-                    is_searching.set(true);
+                        is_searching.set(true);
 
-                    match backend::search_similar_images(selected_directory()).await {
-                        Ok(result) => {
-                            similar_images.write().clear();
-                            similar_images.write().extend(result);
-                        }
-                        Err(e) => {
-                            common::show_toast(e.to_string().as_str(), common::ToastType::Error).await;
+                        match backend::search_similar_images(selected_directory()).await {
+                            Ok(result) => {
+                                similar_images.write().clear();
+                                similar_images.write().extend(result);
+                            }
+                            Err(e) => {
+                                common::show_toast(e.to_string(), common::ToastType::Error);
+                            }
                         }
-                    }
 
-                    is_searching.set(false);
+                        is_searching.set(false);
+                    });
                 },
                 "Search"
             }
         }
 
 
-        SearchResult { is_searching, similar_images, selected_images }
+        SearchResult { is_searching, similar_images, selected_images, on_delete: start_delete }
+        selection::SelectionPolicies { similar_images, selected_images }
 
         div { class: "container p-4",
+            div { class: "flex flex-row items-center gap-4 pb-4",
+                for method in settings_resource().and_then(|r| r.ok()).map(|info| info.delete_methods).unwrap_or_default() {
+                    label { class: "label text-sm",
+                        input {
+                            r#type: "radio",
+                            name: "delete-method",
+                            class: if method == models::DeleteMethod::Permanent { "radio radio-error" } else { "radio" },
+                            checked: delete_method() == method,
+                            onchange: move |_| delete_method.set(method),
+                        }
+                        "{method}"
+                    }
+                }
+            }
+            if delete_method() == models::DeleteMethod::Quarantine {
+                div { class: "flex flex-row items-center pb-4",
+                    input {
+                        r#type: "file",
+                        id: "quarantine-directory-input",
+                        multiple: false,
+                        directory: true,
+                        hidden: true,
+                        onchange: move |e| {
+                            if let Some(file_engine) = e.files() {
+                                let files = file_engine.files();
+                                if !files.is_empty() {
+                                    quarantine_directory.set(files[0].to_string());
+                                }
+                            }
+                        },
+                    }
+                    label {
+                        r#for: "quarantine-directory-input",
+                        class: "btn btn-outline btn-primary btn-sm mr-3",
+                        "Select quarantine directory"
+                    }
+                    div { class: "text-sm text-slate-500 break-all",
+                        if quarantine_directory().is_empty() {
+                            "No directory selected"
+                        } else {
+                            "{quarantine_directory()}"
+                        }
+                    }
+                }
+            }
             button {
                 class: "btn btn-warning w-full",
-                disabled: selected_images().is_empty(),
-                onclick: move |_| async move {
-                    if selected_images().is_empty() {
-                        common::show_toast("Please select images to delete", common::ToastType::Info).await;
-                        return;
-                    }
-
-                    is_confirm_dialog_open.set(true);
-                },
+                disabled: selected_images().is_empty() || is_dry_running() || is_deleting(),
+                onclick: move |_| start_delete(selected_images()),
                 "Delete selected images"
             }
+            if is_deleting() {
+                progress {
+                    class: "progress progress-warning w-full mt-2",
+                    value: "{delete_progress().0}",
+                    max: "{delete_progress().1}",
+                }
+                div { class: "text-xs text-gray-500",
+                    "Processed {delete_progress().0} / {delete_progress().1}"
+                }
+            }
+            button {
+                class: "btn btn-outline w-full mt-2",
+                onclick: move |_| undo(None),
+                "Undo last batch"
+            }
         }
 
+        link_action::LinkAction { similar_images, selected_images }
+        export::ExportResults { selected_directory, similar_images, selected_images }
+
         common::ConfirmDialog {
             title: "Are you sure you want to delete the selected images?".to_string(),
-            message: "Selected {selected_images().len()} images will be deleted.",
+            message: {
+                let passed = dry_run_reports().iter().filter(|r| r.is_ok).count();
+                match delete_method() {
+                    models::DeleteMethod::Trash => format!("{passed} of {} selected images will be moved to the trash.", dry_run_reports().len()),
+                    models::DeleteMethod::Quarantine => format!("{passed} of {} selected images will be moved to {}.", dry_run_reports().len(), quarantine_directory()),
+                    models::DeleteMethod::Permanent => format!("{passed} of {} selected images will be deleted permanently. This can not be undone.", dry_run_reports().len()),
+                }
+            },
             is_open: is_confirm_dialog_open,
+            is_confirm_disabled: !dry_run_reports().iter().any(|r| r.is_ok),
             on_confirm: move |_| async move {
-                let selected_similar_images = selected_images().iter().map(|image_id| {
-                    (image_id.clone(), similar_images().get(image_id).unwrap().filepath.clone())
-                }).collect::<Vec<(u32, String)>>();
-
-                let mut all_success = true;
-                if let Ok(stream) = backend::delete_similar_images_stream(selected_similar_images).await {
-                    let mut stream = stream.into_inner();
-                    while let Some(Ok(progress)) = stream.next().await {
-                        let mut similar_images = similar_images.write();
-                        let similar_image = similar_images.get_mut(&progress.image_id).unwrap();
-                        if progress.is_success {
-                            similar_image.is_deleted = true;
-                        } else {
-                            let message = progress.message.unwrap_or("Unknown error".to_string());
-                            similar_image.error_message = Some(message);
+                // Exactly the files which passed the dry run are deleted
+                let selected_similar_images = dry_run_reports().into_iter().filter(|r| r.is_ok).filter_map(|report| {
+                    Some((report.image_id, report.filepath, report.fingerprint?))
+                }).collect::<Vec<_>>();
+                let method = delete_method();
+                let quarantine_directory = Some(quarantine_directory()).filter(|d| !d.is_empty());
+
+                // The selection and the marks are only changed once the deletion is confirmed
+                let delete_candidates = delete_candidates();
+                selected_images.write().retain(|image_id| !delete_candidates.contains(image_id));
+                marked_images.write().retain(|image_id| !delete_candidates.contains(image_id));
+                is_confirm_dialog_open.set(false);
 
+                // Like the search, the deletion keeps running when another page is opened
+                spawn_forever(async move {
+                    is_deleting.set(true);
+                    delete_progress.set((0, selected_similar_images.len()));
+
+                    let mut all_success = true;
+                    let mut batch_id = None;
+                    match backend::delete_similar_images_stream(selected_similar_images, method, quarantine_directory).await {
+                        Ok(stream) => {
+                            let mut stream = stream.into_inner();
+                            while let Some(progress) = stream.next().await {
+                                let progress = match progress {
+                                    Ok(progress) => progress,
+                                    Err(e) => {
+                                        common::show_toast(e.to_string(), common::ToastType::Error);
+                                        all_success = false;
+                                        break;
+                                    }
+                                };
+
+                                delete_progress.write().0 += 1;
+                                batch_id = Some(progress.batch_id.clone());
+                                if !progress.is_success {
+                                    all_success = false;
+                                }
+                                // A new search may have replaced the results in the meantime
+                                let mut similar_images = similar_images.write();
+                                if let Some(similar_image) = similar_images.get_mut(&progress.image_id) {
+                                    if progress.is_success {
+                                        similar_image.is_deleted = true;
+                                    } else {
+                                        let message = progress.message.unwrap_or("Unknown error".to_string());
+                                        similar_image.error_message = Some(message);
+                                    }
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            common::show_toast(e.to_string(), common::ToastType::Error);
                             all_success = false;
                         }
                     }
-                }
 
-                if all_success {
-                    common::show_toast("All selected images were deleted", common::ToastType::Success).await;
-                } else {
-                    common::show_toast("Failed to delete some selected images", common::ToastType::Error).await;
-                }
+                    if all_success {
+                        match method {
+                            models::DeleteMethod::Trash => common::show_toast_with_action(
+                                "All selected images were moved to the trash",
+                                common::ToastType::Success,
+                                "Undo",
+                                {
+                                    let batch_id = batch_id.clone();
+                                    move || {
+                                        if let Some(batch_id) = batch_id.clone() {
+                                            spawn_forever(undo(Some(batch_id)));
+                                        }
+                                    }
+                                },
+                            ),
+                            models::DeleteMethod::Quarantine => common::show_toast_with_action(
+                                "All selected images were moved to the quarantine",
+                                common::ToastType::Success,
+                                "Undo",
+                                {
+                                    let batch_id = batch_id.clone();
+                                    move || {
+                                        if let Some(batch_id) = batch_id.clone() {
+                                            spawn_forever(undo(Some(batch_id)));
+                                        }
+                                    }
+                                },
+                            ),
+                            models::DeleteMethod::Permanent => {
+                                common::show_toast("All selected images were deleted", common::ToastType::Success);
+                            }
+                        }
+                    } else {
+                        common::show_toast("Failed to delete some selected images", common::ToastType::Error);
+                    }
 
-                selected_images.write().clear();
-                is_confirm_dialog_open.set(false);
+                    is_deleting.set(false);
+                });
             },
             on_cancel: move |_| async move {},
+            ul { class: "max-h-64 overflow-y-auto text-sm",
+                for report in dry_run_reports() {
+                    li { class: "break-all py-1",
+                        if report.is_ok {
+                            "{report.action}"
+                        } else {
+                            span { class: "text-red-500",
+                                "{report.filepath}: {report.message.clone().unwrap_or_default()}"
+                            }
+                        }
+                    }
+                }
+            }
         }
     }
 }
@@ -1,98 +1,482 @@
 use std::collections::BTreeMap;
 use std::collections::HashSet;
+use std::rc::Rc;
 
 use dioxus::prelude::*;
 
 use crate::backend;
 use crate::components::common;
 use crate::models;
-use crate::utils;
+use crate::result_filter::{self, ResultStatus, SortKey};
+
+use super::{comparison::Comparison, SearchState};
+
+// Rows have a fixed height, so only the rows inside the viewport are rendered
+const ROW_HEIGHT: f64 = 80.0;
+const VIEWPORT_HEIGHT: f64 = 640.0;
+const OVERSCAN: usize = 5;
+const LIST_ID: &str = "search-result-list";
+const COLUMNS: &str = "grid grid-cols-[3rem_5rem_minmax(0,1fr)_7rem_14rem] items-center gap-2 px-2";
 
 #[component]
 pub fn SearchResult(
     is_searching: ReadOnlySignal<bool>,
     similar_images: ReadOnlySignal<BTreeMap<u32, models::SimilarImage>>,
     selected_images: Signal<HashSet<u32>>,
+    // Starts the deletion of the selected images
+    on_delete: EventHandler<HashSet<u32>>,
 ) -> Element {
+    let mut compared_image = use_signal(|| None::<models::SimilarImage>);
+    let SearchState {
+        mut filter,
+        mut sort_key,
+        mut is_descending,
+        mut marked_images,
+        ..
+    } = use_context();
+    let mut scroll_top = use_signal(|| 0.0f64);
+    let mut viewport = use_signal(|| None::<Rc<MountedData>>);
+    // Focus and marks are kept by image id, so they survive updates of the results
+    let mut focused_image = use_signal(|| None::<u32>);
+    let mut is_help_open = use_signal(|| false);
+
+    let image_ids = use_memo(move || {
+        result_filter::filter_and_sort(
+            &similar_images.read(),
+            &filter.read(),
+            sort_key(),
+            is_descending(),
+        )
+    });
+    let directories = use_memo(move || result_filter::directories(&similar_images.read()));
+
+    // Selection buttons apply to the shown images which are not deleted
+    let selectable_ids = move || {
+        let similar_images = similar_images.read();
+        image_ids
+            .read()
+            .iter()
+            .copied()
+            .filter(|image_id| similar_images.get(image_id).is_some_and(|i| !i.is_deleted))
+            .collect::<Vec<_>>()
+    };
+
+    let rows = {
+        let similar_images = similar_images.read();
+        let image_ids = image_ids.read();
+        result_filter::visible_range(
+            scroll_top(),
+            VIEWPORT_HEIGHT,
+            ROW_HEIGHT,
+            image_ids.len(),
+            OVERSCAN,
+        )
+        .filter_map(|index| {
+            let image_id = image_ids[index];
+            Some((index, image_id, similar_images.get(&image_id)?.clone()))
+        })
+        .collect::<Vec<_>>()
+    };
+    let mut move_focus = move |offset: isize| {
+        let image_ids = image_ids.read();
+        let Some(image_id) = result_filter::move_focus(&image_ids, focused_image(), offset) else {
+            return;
+        };
+        focused_image.set(Some(image_id));
+
+        let index = image_ids
+            .iter()
+            .position(|i| *i == image_id)
+            .unwrap_or_default();
+        if let Some(top) =
+            result_filter::scroll_into_view(scroll_top(), VIEWPORT_HEIGHT, ROW_HEIGHT, index)
+        {
+            let _ = document::eval(&format!(
+                "document.getElementById('{LIST_ID}').scrollTop = {top};"
+            ));
+        }
+    };
+
+    let handle_key = move |e: KeyboardEvent| {
+        if e.modifiers()
+            .intersects(Modifiers::CONTROL | Modifiers::ALT | Modifiers::META)
+        {
+            return;
+        }
+        // Deleted images can not be selected or marked
+        let focused = focused_image().filter(|image_id| {
+            similar_images
+                .read()
+                .get(image_id)
+                .is_some_and(|i| !i.is_deleted)
+        });
+
+        let key = e.key();
+        let character = match &key {
+            Key::Character(c) => c.as_str(),
+            _ => "",
+        };
+        match (&key, character) {
+            (Key::ArrowDown, _) | (_, "j") => move_focus(1),
+            (Key::ArrowUp, _) | (_, "k") => move_focus(-1),
+            (_, " ") => {
+                if let Some(image_id) = focused {
+                    let mut selected_images = selected_images.write();
+                    if !selected_images.remove(&image_id) {
+                        selected_images.insert(image_id);
+                    }
+                }
+            }
+            (_, "d") => {
+                if let Some(image_id) = focused {
+                    let mut marked_images = marked_images.write();
+                    if !marked_images.remove(&image_id) {
+                        marked_images.insert(image_id);
+                    }
+                }
+            }
+            (_, "o") => {
+                let filepath = focused_image().and_then(|image_id| {
+                    Some(similar_images.read().get(&image_id)?.filepath.clone())
+                });
+                if let Some(filepath) = filepath {
+                    spawn(async move {
+                        if let Err(e) = backend::open_folder_in_explorer(filepath).await {
+                            common::show_toast(e.to_string(), common::ToastType::Error);
+                        }
+                    });
+                }
+            }
+            (_, "c") => {
+                let similar_image = focused_image()
+                    .and_then(|image_id| similar_images.read().get(&image_id).cloned());
+                if similar_image.is_some() {
+                    compared_image.set(similar_image);
+                }
+            }
+            // Marked images are deleted instead of the selection when there are any
+            (Key::Enter, _) => {
+                if marked_images.read().is_empty() {
+                    on_delete.call(selected_images());
+                } else {
+                    on_delete.call(marked_images());
+                }
+            }
+            (_, "?") => is_help_open.toggle(),
+            (Key::Escape, _) => is_help_open.set(false),
+            _ => return,
+        }
+        e.prevent_default();
+    };
+
+    let list_height = image_ids.read().len() as f64 * ROW_HEIGHT;
+    let sort_direction = if is_descending() {
+        "Descending"
+    } else {
+        "Ascending"
+    };
+
     rsx! {
         div { class: "container p-4",
             h1 { class: "text-2xl font-bold mb-4", "Duplicated image search results" }
 
             if is_searching() {
                 common::LoadingSpinner{ message: "Searching for duplicated images..." }
-            } else if similar_images().is_empty() {
+            } else if similar_images.read().is_empty() {
                 div { class: "text-left text-gray-500", "No results found" }
             } else {
-                table { class: "table w-full",
-                    thead {
-                        tr {
-                            th { "Select" }
-                            th { "Thumbnail" }
-                            th { "Filepath" }
-                            th { "Similarity" }
+                div { class: "flex flex-row flex-wrap items-center gap-3 pb-2",
+                    input {
+                        r#type: "text",
+                        class: "input input-sm w-64",
+                        placeholder: "Filter by path",
+                        value: "{filter().path}",
+                        oninput: move |e| filter.write().path = e.value(),
+                    }
+                    div { class: "join items-center",
+                        input {
+                            r#type: "number",
+                            class: "input input-sm join-item w-20",
+                            min: "0",
+                            max: "100",
+                            value: "{filter().min_similarity}",
+                            oninput: move |e| {
+                                if let Ok(min_similarity) = e.value().parse() {
+                                    filter.write().min_similarity = min_similarity;
+                                }
+                            },
                         }
+                        span { class: "join-item px-2 text-sm", "to" }
+                        input {
+                            r#type: "number",
+                            class: "input input-sm join-item w-20",
+                            min: "0",
+                            max: "100",
+                            value: "{filter().max_similarity}",
+                            oninput: move |e| {
+                                if let Ok(max_similarity) = e.value().parse() {
+                                    filter.write().max_similarity = max_similarity;
+                                }
+                            },
+                        }
+                        span { class: "join-item px-2 text-sm", "%" }
                     }
-                    tbody {
-                        {
-                            rsx! {
-                                for (image_id, similar_image) in similar_images() {
-                                    SearchResultRow { image_id, similar_image, selected_images }
+                    select {
+                        class: "select select-sm w-64",
+                        onchange: move |e| filter.write().directory = Some(e.value()).filter(|d| !d.is_empty()),
+                        option { value: "", selected: filter().directory.is_none(), "All directories" }
+                        for directory in directories() {
+                            option {
+                                value: "{directory}",
+                                selected: filter().directory.as_ref() == Some(&directory),
+                                "{directory}"
+                            }
+                        }
+                    }
+                    for status in ResultStatus::ALL {
+                        label { class: "label text-sm",
+                            input {
+                                r#type: "checkbox",
+                                class: "checkbox checkbox-sm",
+                                checked: filter().statuses.contains(&status),
+                                onchange: move |e| {
+                                    if e.checked() {
+                                        filter.write().statuses.insert(status);
+                                    } else {
+                                        filter.write().statuses.remove(&status);
+                                    }
+                                },
+                            }
+                            "{status}"
+                        }
+                    }
+                    div { class: "join",
+                        select {
+                            class: "select select-sm join-item w-40",
+                            onchange: move |e| {
+                                if let Some(key) = e.value().parse::<usize>().ok().and_then(|i| SortKey::ALL.get(i)) {
+                                    sort_key.set(*key);
                                 }
+                            },
+                            for (i, key) in SortKey::ALL.into_iter().enumerate() {
+                                option { value: "{i}", selected: sort_key() == key, "{key}" }
                             }
                         }
+                        button {
+                            class: "btn btn-sm join-item",
+                            title: sort_direction,
+                            onclick: move |_| is_descending.toggle(),
+                            if is_descending() { "↓" } else { "↑" }
+                        }
                     }
                 }
+                div { class: "flex flex-row items-center gap-3 pb-2",
+                    button {
+                        class: "btn btn-sm",
+                        onclick: move |_| selected_images.write().extend(selectable_ids()),
+                        "Select all"
+                    }
+                    button {
+                        class: "btn btn-sm",
+                        onclick: move |_| {
+                            let mut selected_images = selected_images.write();
+                            for image_id in selectable_ids() {
+                                selected_images.remove(&image_id);
+                            }
+                        },
+                        "Select none"
+                    }
+                    button {
+                        class: "btn btn-sm",
+                        onclick: move |_| {
+                            let mut selected_images = selected_images.write();
+                            for image_id in selectable_ids() {
+                                if !selected_images.remove(&image_id) {
+                                    selected_images.insert(image_id);
+                                }
+                            }
+                        },
+                        "Invert selection"
+                    }
+                    span { class: "text-sm text-gray-500",
+                        "{image_ids.read().len()} of {similar_images.read().len()} images shown, {selected_images().len()} selected, {marked_images().len()} marked"
+                    }
+                    button {
+                        class: "btn btn-sm btn-ghost ml-auto",
+                        onclick: move |_| is_help_open.toggle(),
+                        "Keyboard shortcuts (?)"
+                    }
+                }
+
+                div { class: "{COLUMNS} font-semibold text-sm border-b border-base-300 py-2",
+                    div { "Select" }
+                    div { "Thumbnail" }
+                    div { "Filepath" }
+                    div { "Similarity" }
+                    div { "Details" }
+                }
+                div { class: "overflow-y-auto focus:outline-none focus-visible:ring-2 focus-visible:ring-primary",
+                    id: LIST_ID,
+                    tabindex: "0",
+                    style: "height: {VIEWPORT_HEIGHT}px",
+                    onkeydown: handle_key,
+                    onmounted: move |e| viewport.set(Some(e.data())),
+                    onscroll: move |_| async move {
+                        let Some(viewport) = viewport() else {
+                            return;
+                        };
+                        if let Ok(offset) = viewport.get_scroll_offset().await {
+                            scroll_top.set(offset.y);
+                        }
+                    },
+                    div { class: "relative",
+                        style: "height: {list_height}px",
+                        for (index, image_id, similar_image) in rows {
+                            SearchResultRow {
+                                key: "{image_id}",
+                                image_id,
+                                similar_image,
+                                top: index as f64 * ROW_HEIGHT,
+                                selected_images,
+                                marked_images,
+                                focused_image,
+                                compared_image,
+                            }
+                        }
+                    }
+                }
+            }
+            Comparison { compared_image }
+            if is_help_open() {
+                KeyboardShortcuts { is_help_open }
             }
         }
     }
 }
 
+#[component]
+fn KeyboardShortcuts(is_help_open: Signal<bool>) -> Element {
+    let shortcuts = [
+        ("j / ↓", "Focus the next image"),
+        ("k / ↑", "Focus the previous image"),
+        ("Space", "Select or unselect the focused image"),
+        ("d", "Mark or unmark the focused image for deletion"),
+        ("o", "Reveal the focused image in the file manager"),
+        ("c", "Compare the focused image with its reference"),
+        (
+            "Enter",
+            "Delete the marked images, or the selected ones when nothing is marked",
+        ),
+        ("?", "Show or hide this help"),
+    ];
+
+    rsx! {
+        dialog {
+            id: "keyboard_shortcuts_modal",
+            class: "modal modal-open",
+            div { class: "modal-box",
+                h3 { class: "mb-4", "Keyboard shortcuts" }
+                p { class: "text-sm text-gray-500 mb-2", "Click the results list to use them." }
+                table { class: "table table-sm",
+                    tbody {
+                        for (key, description) in shortcuts {
+                            tr {
+                                td { kbd { class: "kbd kbd-sm", "{key}" } }
+                                td { "{description}" }
+                            }
+                        }
+                    }
+                }
+            }
+            form { method: "dialog", class: "modal-backdrop", button { onclick: move |_| is_help_open.set(false), "close" } }
+        }
+    }
+}
+
 #[component]
 pub fn SearchResultRow(
     image_id: u32,
     similar_image: models::SimilarImage,
+    // Offset of the row in the list
+    top: f64,
     selected_images: Signal<HashSet<u32>>,
+    marked_images: Signal<HashSet<u32>>,
+    focused_image: Signal<Option<u32>>,
+    compared_image: Signal<Option<models::SimilarImage>>,
 ) -> Element {
+    let compared = similar_image.clone();
+    let modified_at = similar_image
+        .fingerprint
+        .as_ref()
+        .and_then(|f| f.modified_at)
+        .map(|m| m.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"));
+
     rsx! {
-        tr {
+        div { class: "{COLUMNS} absolute inset-x-0 overflow-hidden border-b border-base-200 hover:bg-base-200 cursor-pointer",
             class: if similar_image.is_deleted && similar_image.error_message.is_none() { "opacity-50" },
             class: if similar_image.error_message.is_some() { "bg-red-100" },
-            td {
+            class: if focused_image() == Some(image_id) { "ring-2 ring-inset ring-primary" },
+            style: "top: {top}px; height: {ROW_HEIGHT}px",
+            onclick: move |_| {
+                focused_image.set(Some(image_id));
+                compared_image.set(Some(compared.clone()));
+            },
+            div {
                 input {
                     r#type: "checkbox",
                     class: "checkbox",
                     disabled: similar_image.is_deleted,
                     checked: selected_images().contains(&image_id),
-                    onclick: move |_| {
+                    onclick: move |e| {
+                        e.stop_propagation();
                         if selected_images().contains(&image_id) {
                             selected_images.write().remove(&image_id);
                         } else {
-                            selected_images.write().insert(image_id.clone());
+                            selected_images.write().insert(image_id);
                         }
                     }
                 }
             }
-            td {
-                img {
-                    src: "{utils::path::normalize_path(&similar_image.filepath)}",
+            div {
+                common::Thumbnail {
+                    path: similar_image.filepath.clone(),
                     class: "w-16 h-16 object-cover",
                 }
             }
-            td { class: "cursor-pointer hover:text-blue-500",
+            div { class: "break-all line-clamp-3 cursor-pointer hover:text-blue-500",
+                // Double-clicking opens the folder, so clicks do not open the comparison
+                onclick: move |e| e.stop_propagation(),
                 ondoubleclick: move |_| {
                     let path = similar_image.filepath.clone();
                     async move {
                         if let Err(e) = backend::open_folder_in_explorer(path).await {
-                            common::show_toast(e.to_string().as_str(), common::ToastType::Error).await;
+                            common::show_toast(e.to_string(), common::ToastType::Error);
                         }
                     }
                 },
                 "{similar_image.filepath}"
             }
-            td {
+            div {
                 "{similar_image.similarity}%"
+                if marked_images().contains(&image_id) {
+                    div { class: "badge badge-error badge-sm", "Marked" }
+                }
                 if let Some(error_message) = &similar_image.error_message {
-                    div { class: "text-red-500 text-sm", "{error_message}"}
+                    div { class: "text-red-500 text-sm line-clamp-2", "{error_message}"}
+                }
+            }
+            div { class: "text-xs text-gray-500",
+                if let (Some(width), Some(height)) = (similar_image.width, similar_image.height) {
+                    div { "{width}x{height}" }
+                }
+                if let Some(fingerprint) = &similar_image.fingerprint {
+                    div { "{fingerprint.size} bytes" }
+                }
+                if let Some(modified_at) = modified_at {
+                    div { "Modified {modified_at}" }
+                }
+                if let Some(matched_reference) = &similar_image.matched_reference {
+                    div { class: "truncate", title: "{matched_reference}", "Matched {matched_reference}" }
                 }
             }
         }
@@ -0,0 +1,89 @@
+use std::collections::{BTreeMap, HashSet};
+
+use dioxus::prelude::*;
+
+use crate::{
+    models,
+    selection_policy::{self, SelectionPolicy},
+};
+
+#[component]
+pub fn SelectionPolicies(
+    similar_images: ReadOnlySignal<BTreeMap<u32, models::SimilarImage>>,
+    selected_images: Signal<HashSet<u32>>,
+) -> Element {
+    // Policies in priority order with whether they are enabled
+    let mut policies = use_signal(|| {
+        SelectionPolicy::ALL
+            .into_iter()
+            .map(|p| (p, false))
+            .collect::<Vec<_>>()
+    });
+    let mut preferred_directory = use_signal(String::new);
+    let mut is_applied = use_signal(|| false);
+
+    rsx! {
+        div { class: "container p-4",
+            h3 { class: "mb-2", "Selection policies" }
+            p { class: "text-xs text-gray-500 mb-2",
+                "One image is kept for each matched reference image and the others are selected. Later policies break the ties of earlier ones."
+            }
+            ul { class: "mb-2",
+                for (i, (policy, is_enabled)) in policies().into_iter().enumerate() {
+                    li { class: "flex flex-row items-center gap-2 py-1",
+                        button {
+                            class: "btn btn-ghost btn-xs",
+                            disabled: i == 0,
+                            onclick: move |_| policies.write().swap(i - 1, i),
+                            "↑"
+                        }
+                        label { class: "label text-sm",
+                            input {
+                                r#type: "checkbox",
+                                class: "checkbox checkbox-sm",
+                                checked: is_enabled,
+                                onclick: move |_| {
+                                    let mut policies = policies.write();
+                                    policies[i].1 = !policies[i].1;
+                                },
+                            }
+                            "{policy}"
+                        }
+                        if policy == SelectionPolicy::PreferredDirectory && is_enabled {
+                            input {
+                                r#type: "text",
+                                class: "input input-sm",
+                                placeholder: "Preferred directory",
+                                value: "{preferred_directory}",
+                                oninput: move |e| preferred_directory.set(e.value()),
+                            }
+                        }
+                    }
+                }
+            }
+            button {
+                class: "btn btn-outline btn-primary btn-sm",
+                disabled: similar_images().is_empty(),
+                onclick: move |_| {
+                    let enabled_policies = policies()
+                        .into_iter()
+                        .filter(|(_, is_enabled)| *is_enabled)
+                        .map(|(p, _)| p)
+                        .collect::<Vec<_>>();
+                    selected_images.set(selection_policy::select_duplicates(
+                        &similar_images(),
+                        &enabled_policies,
+                        &preferred_directory(),
+                    ));
+                    is_applied.set(true);
+                },
+                "Apply policies"
+            }
+            if is_applied() {
+                p { class: "text-sm text-gray-500 mt-2",
+                    "Selected {selected_images().len()} images. Please review the selection before deleting or linking them."
+                }
+            }
+        }
+    }
+}
@@ -1,3 +1,31 @@
+use dioxus::prelude::*;
+
+use history::History;
+use home::Home;
+use quarantine::Quarantine;
+use reference_images::ReferenceImages;
+use settings::Settings;
+
 pub mod common;
+mod history;
 pub mod home;
-mod reference_images;
+mod navbar;
+mod quarantine;
+pub mod reference_images;
+mod settings;
+
+#[derive(Debug, Clone, PartialEq, Routable)]
+#[rustfmt::skip]
+pub enum Route {
+    #[layout(navbar::Navbar)]
+        #[route("/")]
+        Home {},
+        #[route("/references")]
+        ReferenceImages {},
+        #[route("/history")]
+        History {},
+        #[route("/quarantine")]
+        Quarantine {},
+        #[route("/settings")]
+        Settings {},
+}
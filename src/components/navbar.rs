@@ -0,0 +1,24 @@
+use dioxus::prelude::*;
+
+use super::{common, Route};
+
+#[component]
+pub fn Navbar() -> Element {
+    rsx! {
+        div { class: "navbar bg-base-100 border-b px-4 justify-between",
+            div { class: "flex flex-row gap-2",
+                for (route, label) in [
+                    (Route::Home {}, "Search"),
+                    (Route::ReferenceImages {}, "References"),
+                    (Route::History {}, "History"),
+                    (Route::Quarantine {}, "Quarantine"),
+                    (Route::Settings {}, "Settings"),
+                ] {
+                    Link { class: "btn btn-ghost btn-sm", active_class: "btn-active", to: route, "{label}" }
+                }
+            }
+            common::NotificationHistory {}
+        }
+        Outlet::<Route> {}
+    }
+}
@@ -0,0 +1,59 @@
+use crate::{backend, components::common};
+use dioxus::prelude::*;
+
+#[component]
+pub fn QuarantineEntryCard(
+    quarantine_entry: entity::quarantine_entry::Model,
+    quarantine_entries_resource: Resource<
+        Result<Vec<entity::quarantine_entry::Model>, ServerFnError>,
+    >,
+) -> Element {
+    let entity::quarantine_entry::Model {
+        id,
+        original_path,
+        quarantine_path,
+        created_at,
+        ..
+    } = quarantine_entry;
+
+    let created_at = created_at.format("%Y-%m-%d %H:%M");
+
+    rsx! {
+        div { class: "card card-side card-border border-2 mb-4",
+            id: "quarantine-{id}",
+            figure { class: "w-24 shrink-0",
+                common::Thumbnail {
+                    path: quarantine_path.clone(),
+                    class: "rounded-lg object-cover",
+                }
+            }
+            div { class: "card-body py-3",
+                div { class: "space-y-1",
+                    p { class: "text-base font-semibold break-all", "{original_path}" }
+                    p { class: "text-xs text-gray-500 break-all", "{quarantine_path}" }
+                }
+                div { class: "flex flex-row justify-between items-center mt-2",
+                    div { class: "text-xs text-gray-500",
+                        p { "Quarantined: {created_at}" }
+                    }
+                    button { class: "btn btn-primary",
+                        onclick: move |_| async move {
+                            match backend::restore_quarantine_entries(vec![id]).await {
+                                Ok(results) => {
+                                    for message in results.into_iter().filter_map(|r| r.message) {
+                                        common::show_toast(message, common::ToastType::Error);
+                                    }
+                                    quarantine_entries_resource.restart();
+                                }
+                                Err(e) => {
+                                    common::show_toast(e.to_string(), common::ToastType::Error);
+                                }
+                            }
+                        },
+                        "Restore"
+                    }
+                }
+            }
+        }
+    }
+}
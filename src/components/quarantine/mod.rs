@@ -0,0 +1,111 @@
+use crate::{backend, components::common};
+use dioxus::prelude::*;
+
+mod entry;
+
+#[component]
+pub fn Quarantine() -> Element {
+    let mut is_processing = use_signal(|| false);
+    let mut purge_days = use_signal(|| 30u32);
+
+    let mut quarantine_entries_resource = use_server_future(backend::get_quarantine_entries)?;
+
+    rsx! {
+        div { class: "container p-4",
+            h3 { class: "mb-4", "Quarantine" }
+            div { class: "flex flex-row items-center gap-3 pb-4",
+                button {
+                    class: "btn btn-primary",
+                    disabled: is_processing(),
+                    onclick: move |_| async move {
+                        let Some(Ok(quarantine_entries)) = quarantine_entries_resource() else {
+                            return;
+                        };
+                        if quarantine_entries.is_empty() {
+                            common::show_toast("No quarantined images", common::ToastType::Info);
+                            return;
+                        }
+
+                        is_processing.set(true);
+
+                        let ids = quarantine_entries.iter().map(|e| e.id).collect::<Vec<_>>();
+                        match backend::restore_quarantine_entries(ids).await {
+                            Ok(results) if results.iter().all(|r| r.is_success) => {
+                                common::show_toast("All quarantined images were restored", common::ToastType::Success);
+                            }
+                            Ok(results) => {
+                                for message in results.into_iter().filter_map(|r| r.message) {
+                                    common::show_toast(message, common::ToastType::Error);
+                                }
+                            }
+                            Err(e) => {
+                                common::show_toast(e.to_string(), common::ToastType::Error);
+                            }
+                        }
+
+                        is_processing.set(false);
+                        quarantine_entries_resource.restart();
+                    },
+                    "Restore all"
+                }
+                div { class: "join",
+                    input {
+                        r#type: "number",
+                        class: "input input-sm join-item w-20",
+                        min: "0",
+                        value: "{purge_days}",
+                        oninput: move |e| {
+                            if let Ok(days) = e.value().parse() {
+                                purge_days.set(days);
+                            }
+                        },
+                    }
+                    button {
+                        class: "btn btn-sm btn-error join-item",
+                        disabled: is_processing(),
+                        onclick: move |_| async move {
+                            is_processing.set(true);
+
+                            match backend::purge_quarantine_entries(purge_days()).await {
+                                Ok(results) if results.iter().all(|r| r.is_success) => {
+                                    common::show_toast(format!("Purged {} quarantined images", results.len()), common::ToastType::Success);
+                                }
+                                Ok(_) => {
+                                    common::show_toast("Failed to purge some quarantined images", common::ToastType::Error);
+                                }
+                                Err(e) => {
+                                    common::show_toast(e.to_string(), common::ToastType::Error);
+                                }
+                            }
+
+                            is_processing.set(false);
+                            quarantine_entries_resource.restart();
+                        },
+                        "Purge older than {purge_days} days"
+                    }
+                }
+            }
+            div {
+                if is_processing() {
+                    common::LoadingSpinner { message: "Processing quarantined images..." }
+                } else {
+                    match quarantine_entries_resource() {
+                        Some(Ok(quarantine_entries)) => rsx! {
+                            if quarantine_entries.is_empty() {
+                                p { class: "text-xs text-gray-500", "No quarantined images." }
+                            } else {
+                                for quarantine_entry in quarantine_entries {
+                                    entry::QuarantineEntryCard { quarantine_entry, quarantine_entries_resource }
+                                }
+                            }
+                        },
+                        Some(Err(e)) => rsx! {
+                            div { role: "alert", class: "alert alert-error", span { "{e}" } }
+                        },
+                        None => rsx! {},
+                    }
+                }
+            }
+        }
+    }
+}
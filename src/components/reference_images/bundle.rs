@@ -0,0 +1,182 @@
+use std::collections::HashSet;
+
+use crate::{backend, bundle, components::common, models::reference_image::ReferenceImagePage};
+use dioxus::prelude::*;
+
+#[component]
+pub fn ReferenceBundle(
+    selected_reference_images: Signal<HashSet<i32>>,
+    reference_image_page_resource: Resource<Result<ReferenceImagePage, ServerFnError>>,
+) -> Element {
+    let mut selected_collections = use_signal(HashSet::<String>::new);
+    let mut output_directory = use_signal(String::new);
+    let mut encoding = use_signal(|| bundle::BundleEncoding::Json);
+    let mut include_thumbnails = use_signal(|| true);
+    let mut is_processing = use_signal(|| false);
+    let mut import_report = use_signal(|| None::<bundle::ImportReport>);
+
+    let mut collections_resource = use_server_future(backend::get_reference_collections)?;
+
+    rsx! {
+        div { class: "container p-4",
+            h3 { class: "mb-4", "Export reference images" }
+            p { class: "text-xs text-gray-500 mb-2",
+                "Selected {selected_reference_images().len()} reference images."
+            }
+            if let Some(Ok(collections)) = collections_resource() {
+                if !collections.is_empty() {
+                    div { class: "flex flex-row flex-wrap gap-3 mb-2",
+                        for collection in collections {
+                            label { class: "label text-sm",
+                                input {
+                                    r#type: "checkbox",
+                                    class: "checkbox checkbox-sm",
+                                    checked: selected_collections().contains(&collection),
+                                    onclick: {
+                                        let collection = collection.clone();
+                                        move |_| {
+                                            if selected_collections().contains(&collection) {
+                                                selected_collections.write().remove(&collection);
+                                            } else {
+                                                selected_collections.write().insert(collection.clone());
+                                            }
+                                        }
+                                    },
+                                }
+                                "{collection}"
+                            }
+                        }
+                    }
+                }
+            }
+            div { class: "flex flex-row items-center gap-3 pb-2",
+                select {
+                    class: "select select-sm w-32",
+                    onchange: move |e| {
+                        encoding.set(if e.value() == "cbor" { bundle::BundleEncoding::Cbor } else { bundle::BundleEncoding::Json });
+                    },
+                    option { value: "json", selected: encoding() == bundle::BundleEncoding::Json, "JSON" }
+                    option { value: "cbor", selected: encoding() == bundle::BundleEncoding::Cbor, "CBOR" }
+                }
+                label { class: "label text-sm",
+                    input {
+                        r#type: "checkbox",
+                        class: "checkbox checkbox-sm",
+                        checked: include_thumbnails(),
+                        onclick: move |_| include_thumbnails.toggle(),
+                    }
+                    "Include thumbnails"
+                }
+            }
+            div { class: "flex flex-row items-center pb-2",
+                input {
+                    r#type: "file",
+                    id: "bundle-output-directory-input",
+                    multiple: false,
+                    directory: true,
+                    hidden: true,
+                    onchange: move |e| {
+                        if let Some(file_engine) = e.files() {
+                            let files = file_engine.files();
+                            if !files.is_empty() {
+                                output_directory.set(files[0].to_string());
+                            }
+                        }
+                    },
+                }
+                label {
+                    r#for: "bundle-output-directory-input",
+                    class: "btn btn-outline btn-primary btn-sm mr-3",
+                    "Select output directory"
+                }
+                div { class: "text-sm text-slate-500 break-all",
+                    if output_directory().is_empty() {
+                        "No directory selected"
+                    } else {
+                        "{output_directory()}"
+                    }
+                }
+            }
+            button {
+                class: "btn btn-primary w-full mb-4",
+                disabled: is_processing() || output_directory().is_empty()
+                    || (selected_reference_images().is_empty() && selected_collections().is_empty()),
+                onclick: move |_| async move {
+                    is_processing.set(true);
+
+                    match backend::export_reference_bundle(
+                        selected_reference_images().into_iter().collect(),
+                        selected_collections().into_iter().collect(),
+                        output_directory(),
+                        encoding(),
+                        include_thumbnails(),
+                    )
+                    .await
+                    {
+                        Ok(path) => {
+                            common::show_toast(format!("Exported to {path}"), common::ToastType::Success);
+                        }
+                        Err(e) => {
+                            common::show_toast(e.to_string(), common::ToastType::Error);
+                        }
+                    }
+
+                    is_processing.set(false);
+                },
+                "Export bundle"
+            }
+
+            h3 { class: "mb-4", "Import reference images" }
+            div { class: "flex flex-row items-center pb-2",
+                input {
+                    r#type: "file",
+                    id: "bundle-import-input",
+                    accept: ".{bundle::BUNDLE_EXTENSION}",
+                    multiple: false,
+                    hidden: true,
+                    disabled: is_processing(),
+                    onchange: move |e| async move {
+                        let Some(file_engine) = e.files() else {
+                            return;
+                        };
+                        let Some(bundle_path) = file_engine.files().first().cloned() else {
+                            return;
+                        };
+
+                        is_processing.set(true);
+
+                        match backend::import_reference_bundle(bundle_path).await {
+                            Ok(report) => {
+                                import_report.set(Some(report));
+                                collections_resource.restart();
+                                reference_image_page_resource.restart();
+                            }
+                            Err(e) => {
+                                common::show_toast(e.to_string(), common::ToastType::Error);
+                            }
+                        }
+
+                        is_processing.set(false);
+                    },
+                }
+                label {
+                    r#for: "bundle-import-input",
+                    class: "btn btn-outline btn-primary btn-sm mr-3",
+                    "Import bundle"
+                }
+            }
+            if let Some(report) = import_report() {
+                div { class: "text-sm",
+                    p { "Imported: {report.imported}, duplicates skipped: {report.duplicates}, conflicts: {report.conflicts.len()}" }
+                    if !report.conflicts.is_empty() {
+                        ul { class: "mt-2 text-red-500",
+                            for conflict in report.conflicts {
+                                li { class: "break-all", "{conflict.original_path}: {conflict.reason}" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
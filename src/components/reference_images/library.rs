@@ -0,0 +1,304 @@
+use std::{collections::HashSet, path::Path};
+
+use crate::{
+    backend,
+    components::common,
+    image_processing,
+    models::reference_image::{
+        FileStatus, ReferenceImageEntry, ReferenceImagePage, ReferenceImageQuery,
+        ReferenceImageSortKey,
+    },
+};
+use dioxus::prelude::*;
+
+// Grid of the registered reference images, paged and filtered by the server
+// The selection is kept while paging, so bulk actions can span pages
+#[component]
+pub fn ReferenceLibrary(
+    query: Signal<ReferenceImageQuery>,
+    selected_reference_images: Signal<HashSet<i32>>,
+    reference_image_page_resource: Resource<Result<ReferenceImagePage, ServerFnError>>,
+    is_registering: Signal<bool>,
+) -> Element {
+    let mut target_collection = use_signal(String::new);
+    let mut is_processing = use_signal(|| false);
+    let mut is_confirm_dialog_open = use_signal(|| false);
+
+    let collections_resource = use_server_future(backend::get_reference_collections)?;
+
+    // Filters go back to the first page
+    let mut update_query = move |update: &dyn Fn(&mut ReferenceImageQuery)| {
+        let mut query = query.write();
+        update(&mut query);
+        query.page = 0;
+    };
+    let created_from = query()
+        .created_from
+        .map(|d| d.to_string())
+        .unwrap_or_default();
+    let created_to = query()
+        .created_to
+        .map(|d| d.to_string())
+        .unwrap_or_default();
+
+    rsx! {
+        div { class: "container p-4",
+            h3 { class: "mb-4", "Registered reference images" }
+            div { class: "flex flex-row flex-wrap items-center gap-2 mb-2",
+                input {
+                    r#type: "text",
+                    class: "input input-sm w-64",
+                    placeholder: "Filter by path",
+                    value: "{query().path}",
+                    oninput: move |e| update_query(&|q| q.path = e.value()),
+                }
+                select {
+                    class: "select select-sm w-40",
+                    onchange: move |e| update_query(&|q| q.collection = Some(e.value()).filter(|c| !c.is_empty())),
+                    option { value: "", selected: query().collection.is_none(), "All collections" }
+                    if let Some(Ok(collections)) = collections_resource() {
+                        for collection in collections {
+                            option { value: "{collection}", selected: query().collection.as_ref() == Some(&collection), "{collection}" }
+                        }
+                    }
+                }
+                select {
+                    class: "select select-sm w-40",
+                    onchange: move |e| {
+                        let file_status = match e.value().as_str() {
+                            "present" => FileStatus::Present,
+                            "missing" => FileStatus::Missing,
+                            _ => FileStatus::Any,
+                        };
+                        update_query(&|q| q.file_status = file_status);
+                    },
+                    option { value: "any", selected: query().file_status == FileStatus::Any, "{FileStatus::Any}" }
+                    option { value: "present", selected: query().file_status == FileStatus::Present, "{FileStatus::Present}" }
+                    option { value: "missing", selected: query().file_status == FileStatus::Missing, "{FileStatus::Missing}" }
+                }
+            }
+            div { class: "flex flex-row flex-wrap items-center gap-2 mb-4",
+                label { class: "label text-sm", "Registered from" }
+                input {
+                    r#type: "date",
+                    class: "input input-sm w-40",
+                    value: "{created_from}",
+                    oninput: move |e| update_query(&|q| q.created_from = e.value().parse().ok()),
+                }
+                label { class: "label text-sm", "to" }
+                input {
+                    r#type: "date",
+                    class: "input input-sm w-40",
+                    value: "{created_to}",
+                    oninput: move |e| update_query(&|q| q.created_to = e.value().parse().ok()),
+                }
+                select {
+                    class: "select select-sm w-32",
+                    onchange: move |e| {
+                        if let Some(sort_key) = e.value().parse::<usize>().ok().and_then(|i| ReferenceImageSortKey::ALL.get(i)) {
+                            update_query(&|q| q.sort_key = *sort_key);
+                        }
+                    },
+                    for (i, sort_key) in ReferenceImageSortKey::ALL.into_iter().enumerate() {
+                        option { value: "{i}", selected: query().sort_key == sort_key, "{sort_key}" }
+                    }
+                }
+                button {
+                    class: "btn btn-sm btn-ghost",
+                    onclick: move |_| update_query(&|q| q.is_descending = !q.is_descending),
+                    if query().is_descending { "↓" } else { "↑" }
+                }
+            }
+
+            // Bulk actions
+            div { class: "flex flex-row flex-wrap items-center gap-2 mb-4",
+                span { class: "text-sm", "{selected_reference_images().len()} selected" }
+                button {
+                    class: "btn btn-sm",
+                    onclick: move |_| {
+                        if let Some(Ok(page)) = reference_image_page_resource() {
+                            selected_reference_images.write().extend(page.entries.iter().map(|e| e.reference_image.id));
+                        }
+                    },
+                    "Select page"
+                }
+                button {
+                    class: "btn btn-sm",
+                    onclick: move |_| selected_reference_images.write().clear(),
+                    "Clear selection"
+                }
+                div { class: "join",
+                    input {
+                        r#type: "text",
+                        class: "input input-sm join-item w-40",
+                        placeholder: "Collection",
+                        value: "{target_collection}",
+                        oninput: move |e| target_collection.set(e.value()),
+                    }
+                    button {
+                        class: "btn btn-sm join-item",
+                        disabled: is_processing() || selected_reference_images().is_empty(),
+                        onclick: move |_| async move {
+                            is_processing.set(true);
+                            let collection = Some(target_collection().trim().to_string()).filter(|c| !c.is_empty());
+                            match backend::move_reference_images_to_collection(selected_reference_images().into_iter().collect(), collection).await {
+                                Ok(updated) => {
+                                    common::show_toast(format!("Moved {updated} reference images"), common::ToastType::Success);
+                                }
+                                Err(e) => {
+                                    common::show_toast(e.to_string(), common::ToastType::Error);
+                                }
+                            }
+                            is_processing.set(false);
+                            reference_image_page_resource.restart();
+                        },
+                        "Move to collection"
+                    }
+                }
+                button {
+                    class: "btn btn-sm btn-error",
+                    disabled: is_processing() || selected_reference_images().is_empty(),
+                    onclick: move |_| is_confirm_dialog_open.set(true),
+                    "Unregister selected"
+                }
+            }
+
+            if is_registering() {
+                common::LoadingSpinner { message: "Registering reference images..." }
+            } else {
+                match reference_image_page_resource() {
+                    Some(Ok(page)) => {
+                        let page_count = page.total.div_ceil(query().page_size).max(1);
+                        let current_page = query().page;
+                        rsx! {
+                            if page.entries.is_empty() {
+                                p { class: "text-xs text-error", "No registered reference images. Please register at least one image." }
+                            } else {
+                                p { class: "text-xs text-gray-500 mb-2", "{page.total} reference images" }
+                                div { class: "grid grid-cols-2 sm:grid-cols-4 lg:grid-cols-6 gap-3 mb-4",
+                                    for entry in page.entries {
+                                        ReferenceImageTile { key: "{entry.reference_image.id}", entry, selected_reference_images }
+                                    }
+                                }
+                            }
+                            div { class: "join flex justify-center",
+                                button {
+                                    class: "btn btn-sm join-item",
+                                    disabled: current_page == 0,
+                                    onclick: move |_| query.write().page = current_page - 1,
+                                    "«"
+                                }
+                                button { class: "btn btn-sm join-item", "Page {current_page + 1} of {page_count}" }
+                                button {
+                                    class: "btn btn-sm join-item",
+                                    disabled: current_page + 1 >= page_count,
+                                    onclick: move |_| query.write().page = current_page + 1,
+                                    "»"
+                                }
+                            }
+                        }
+                    },
+                    Some(Err(e)) => rsx! {
+                        div { role: "alert", class: "alert alert-error",
+                            svg {
+                                xmlns: "http://www.w3.org/2000/svg",
+                                fill: "none",
+                                "viewBox": "0 0 24 24",
+                                class: "h-6 w-6 shrink-0 stroke-current",
+                                path {
+                                    "stroke-width": "2",
+                                    "stroke-linejoin": "round",
+                                    d: "M10 14l2-2m0 0l2-2m-2 2l-2-2m2 2l2 2m7-2a9 9 0 11-18 0 9 9 0 0118 0z",
+                                    "stroke-linecap": "round",
+                                }
+                            }
+                            span { "Error! Task failed successfully. {e}" }
+                        }
+                    },
+                    None => rsx! {
+                        common::LoadingSpinner { message: "Loading reference images..." }
+                    },
+                }
+            }
+        }
+
+        common::ConfirmDialog {
+            title: "Are you sure you want to unregister the selected reference images?".to_string(),
+            message: format!("{} reference images will be unregistered. The image files are kept.", selected_reference_images().len()),
+            is_open: is_confirm_dialog_open,
+            on_confirm: move |_| async move {
+                is_processing.set(true);
+                match backend::delete_registered_reference_images(selected_reference_images().into_iter().collect()).await {
+                    Ok(deleted) => {
+                        common::show_toast(format!("Unregistered {deleted} reference images"), common::ToastType::Success);
+                        selected_reference_images.write().clear();
+                    }
+                    Err(e) => {
+                        common::show_toast(e.to_string(), common::ToastType::Error);
+                    }
+                }
+                is_processing.set(false);
+                reference_image_page_resource.restart();
+            },
+            on_cancel: move |_| async move {},
+        }
+    }
+}
+
+#[component]
+fn ReferenceImageTile(
+    entry: ReferenceImageEntry,
+    selected_reference_images: Signal<HashSet<i32>>,
+) -> Element {
+    let ReferenceImageEntry {
+        reference_image,
+        is_missing,
+    } = entry;
+    let id = reference_image.id;
+    let filename = Path::new(&reference_image.filepath)
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let created_at = reference_image.created_at.format("%Y-%m-%d %H:%M");
+
+    rsx! {
+        div {
+            class: "card card-border border-2 cursor-pointer",
+            class: if selected_reference_images().contains(&id) { "border-primary" },
+            title: "{reference_image.filepath}",
+            onclick: move |_| {
+                if selected_reference_images().contains(&id) {
+                    selected_reference_images.write().remove(&id);
+                } else {
+                    selected_reference_images.write().insert(id);
+                }
+            },
+            figure { class: "relative h-28 bg-base-200",
+                input {
+                    r#type: "checkbox",
+                    class: "checkbox checkbox-sm absolute top-1 left-1 pointer-events-none",
+                    checked: selected_reference_images().contains(&id),
+                }
+                if !is_missing {
+                    common::Thumbnail {
+                        path: reference_image.filepath.clone(),
+                        class: "h-full w-full object-cover",
+                    }
+                }
+            }
+            div { class: "p-2 space-y-1",
+                p { class: "text-xs font-semibold break-all", "{filename}" }
+                p { class: "text-xs text-gray-500", "{created_at}" }
+                if let Some(collection) = &reference_image.collection {
+                    div { class: "badge badge-outline badge-xs mr-1", "{collection}" }
+                }
+                if is_missing {
+                    div { class: "badge badge-error badge-xs mr-1", "Missing" }
+                }
+                if !image_processing::is_current_hash_version(reference_image.hash_version) {
+                    div { class: "badge badge-warning badge-xs", "Needs re-hash" }
+                }
+            }
+        }
+    }
+}
@@ -1,131 +1,234 @@
-use crate::{backend, components::common};
-use dioxus::{logger::tracing::error, prelude::*};
+use std::collections::HashSet;
 
-mod card;
+use crate::{
+    backend::{self, RegistrationEvent},
+    components::common,
+    models::reference_image::ReferenceImageQuery,
+};
+use dioxus::{html::HasFileData, prelude::*};
+use futures::StreamExt;
+
+mod bundle;
+mod library;
+mod rehash;
+
+// State of the reference images page which is kept while other pages are shown
+#[derive(Clone, Copy)]
+pub struct ReferenceImagesState {
+    pub selected_files: Signal<Vec<String>>,
+    pub collection: Signal<String>,
+    pub is_registering: Signal<bool>,
+    pub selected_reference_images: Signal<HashSet<i32>>,
+    pub query: Signal<ReferenceImageQuery>,
+    // Progress of the running or last registration
+    pub processed: Signal<usize>,
+    pub total: Signal<usize>,
+    pub inserted: Signal<usize>,
+    pub updated: Signal<usize>,
+    pub failures: Signal<Vec<(String, String)>>,
+}
+
+impl Default for ReferenceImagesState {
+    fn default() -> Self {
+        Self {
+            selected_files: Signal::new(Vec::new()),
+            collection: Signal::new(String::new()),
+            is_registering: Signal::new(false),
+            selected_reference_images: Signal::new(HashSet::new()),
+            query: Signal::new(ReferenceImageQuery::default()),
+            processed: Signal::new(0),
+            total: Signal::new(0),
+            inserted: Signal::new(0),
+            updated: Signal::new(0),
+            failures: Signal::new(Vec::new()),
+        }
+    }
+}
 
 #[component]
 pub fn ReferenceImages() -> Element {
-    let mut is_modal_open = use_signal(|| false);
-    let mut selected_files = use_signal(Vec::<String>::new);
-    let mut is_registering = use_signal(|| false);
+    let ReferenceImagesState {
+        mut selected_files,
+        mut collection,
+        mut is_registering,
+        selected_reference_images,
+        query,
+        mut processed,
+        mut total,
+        mut inserted,
+        mut updated,
+        mut failures,
+    } = use_context();
+    let mut is_dragging = use_signal(|| false);
 
-    let mut registered_reference_images_resource =
-        use_server_future(backend::get_registered_reference_images)?;
+    // The page is loaded again when a registration starts or finishes,
+    // which may happen while another page is shown
+    let reference_image_page_resource = use_server_future(move || {
+        is_registering();
+        backend::get_reference_image_page(query())
+    })?;
 
-    rsx! {
-        button {
-            class: "btn btn-accent",
-            onclick: move |_| {
-                is_modal_open.toggle();
-                selected_files.write().clear();
-            },
-            "Check reference images"
-        }
-        dialog {
-            id: "register_reference_images_modal",
-            class: "modal",
-            class: if is_modal_open() { "modal-open" },
-            div { class: "modal-box max-w-2xl",
-                div { class: "container p-4",
-                    h3 { class: "mb-4", "Register reference images" }
-                    div { class: "flex flex-row items-center pb-4",
-                        input {
-                            r#type: "file",
-                            id: "reference-images-input",
-                            multiple: true,
-                            hidden: true,
-                            onchange: move |e| {
-                                if let Some(file_engine) = e.files() {
-                                    let files = file_engine.files();
-                                    selected_files.write().extend(files.iter().map(|file| file.to_string()));
-                                }
-                            },
-                        }
-                        label {
-                            r#for: "reference-images-input",
-                            class: "btn btn-outline btn-primary mr-3",
-                            "Select files"
+    let register_files = move |files: Vec<String>| async move {
+        processed.set(0);
+        total.set(files.len());
+        inserted.set(0);
+        updated.set(0);
+        failures.write().clear();
+
+        match backend::register_reference_images_stream(
+            files,
+            Some(collection().trim().to_string()).filter(|c| !c.is_empty()),
+        )
+        .await
+        {
+            Ok(stream) => {
+                let mut stream = stream.into_inner();
+                let mut stream_error = None;
+                while let Some(progress) = stream.next().await {
+                    let progress = match progress {
+                        Ok(progress) => progress,
+                        Err(e) => {
+                            stream_error = Some(e);
+                            break;
                         }
-                        label {
-                            class: "text-sm text-slate-500",
-                            if selected_files().len() == 0 {
-                                "No files selected"
-                            } else {
-                                {selected_files().join(", ")}
-                            }
+                    };
+                    processed.set(progress.processed);
+                    total.set(progress.total);
+                    match progress.event {
+                        RegistrationEvent::Hashed => {}
+                        RegistrationEvent::Inserted => inserted += 1,
+                        RegistrationEvent::Updated => updated += 1,
+                        RegistrationEvent::Failed => {
+                            let message = progress.message.unwrap_or("Unknown error".to_string());
+                            failures.write().push((progress.filepath, message));
                         }
                     }
-                    button {
-                        class: "btn btn-primary w-full mb-4",
-                        disabled: is_registering(),
-                        onclick: move |_| async move {
-                            if selected_files().is_empty() {
-                                common::show_toast("Please select files to register", common::ToastType::Info).await;
-                                return;
-                            }
+                }
 
-                            is_registering.set(true);
+                if let Some(e) = stream_error {
+                    common::show_toast(e.to_string(), common::ToastType::Error);
+                } else if failures().is_empty() {
+                    common::show_toast(
+                        format!("Registered {} reference images", inserted() + updated()),
+                        common::ToastType::Success,
+                    );
+                } else {
+                    common::show_toast(
+                        "Failed to register some reference images",
+                        common::ToastType::Warning,
+                    );
+                }
+            }
+            Err(e) => {
+                common::show_toast(e.to_string(), common::ToastType::Error);
+            }
+        }
 
-                            if let Err(e) = backend::register_reference_images(selected_files()).await {
-                                error!("Failed to register reference images: {}", e);
-                            }
+        is_registering.set(false);
+    };
 
-                            selected_files.write().clear();
-                            is_registering.set(false);
-                            registered_reference_images_resource.restart();
+    // Shared by the register button and dropped files
+    // Like the search, the registration keeps running when another page is opened
+    let mut register = move |files: Vec<String>| {
+        if is_registering() {
+            return;
+        }
+        is_registering.set(true);
+        spawn_forever(register_files(files));
+    };
+
+    rsx! {
+        div { class: "container max-w-4xl",
+            class: if is_dragging() { "outline-dashed outline-2 outline-primary" },
+            ondragover: move |e| {
+                e.prevent_default();
+                is_dragging.set(true);
+            },
+            ondragleave: move |_| is_dragging.set(false),
+            ondrop: move |e| async move {
+                e.prevent_default();
+                is_dragging.set(false);
+                let files = e.files().map(|file_engine| file_engine.files()).unwrap_or_default();
+                if !files.is_empty() {
+                    register(files);
+                }
+            },
+            div { class: "container p-4",
+                h3 { class: "mb-4", "Register reference images" }
+                p { class: "text-xs text-slate-500 mb-2", "Select files or drop them anywhere on this page to register them." }
+                div { class: "flex flex-row items-center pb-4",
+                    input {
+                        r#type: "file",
+                        id: "reference-images-input",
+                        multiple: true,
+                        hidden: true,
+                        onchange: move |e| {
+                            if let Some(file_engine) = e.files() {
+                                let files = file_engine.files();
+                                selected_files.write().extend(files.iter().map(|file| file.to_string()));
+                            }
                         },
-                        "Register selected files"
+                    }
+                    label {
+                        r#for: "reference-images-input",
+                        class: "btn btn-outline btn-primary mr-3",
+                        "Select files"
+                    }
+                    label {
+                        class: "text-sm text-slate-500",
+                        if selected_files().is_empty() {
+                            "No files selected"
+                        } else {
+                            {selected_files().join(", ")}
+                        }
                     }
                 }
-                div { class: "container p-4",
-                    h3 { class: "mb-4", "Registered reference images" }
-                    div {
-                        {
-                            if is_registering() {
-                                rsx! {
-                                    common::LoadingSpinner { message: "Registering reference images..."}
-                                }
-                            } else {
-                                rsx! {
-                                    match registered_reference_images_resource.unwrap() {
-                                        Ok(reference_images) => {
-                                            rsx! {
-                                                if reference_images.len() > 0 {
-                                                    for reference_image in reference_images {
-                                                        card::RegisteredReferenceImageCard { reference_image, registered_reference_images_resource }
-                                                    }
-                                                } else {
-                                                    p { class: "text-xs text-error", "No registered reference images. Please register at least one image." }
-                                                }
-                                            }
-                                        },
-                                        Err(e) => {
-                                            rsx! {
-                                                div { role: "alert", class: "alert alert-error",
-                                                    svg {
-                                                        xmlns: "http://www.w3.org/2000/svg",
-                                                        fill: "none",
-                                                        "viewBox": "0 0 24 24",
-                                                        class: "h-6 w-6 shrink-0 stroke-current",
-                                                        path {
-                                                            "stroke-width": "2",
-                                                            "stroke-linejoin": "round",
-                                                            d: "M10 14l2-2m0 0l2-2m-2 2l-2-2m2 2l2 2m7-2a9 9 0 11-18 0 9 9 0 0118 0z",
-                                                            "stroke-linecap": "round",
-                                                        }
-                                                    }
-                                                    span { "Error! Task failed successfully. {e}" }
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
+                input {
+                    r#type: "text",
+                    class: "input input-sm w-full mb-4",
+                    placeholder: "Collection (optional)",
+                    value: "{collection}",
+                    oninput: move |e| collection.set(e.value()),
+                }
+                button {
+                    class: "btn btn-primary w-full mb-4",
+                    disabled: is_registering(),
+                    onclick: move |_| {
+                        if selected_files().is_empty() {
+                            common::show_toast("Please select files to register", common::ToastType::Info);
+                            return;
+                        }
+
+                        register(selected_files());
+                        selected_files.write().clear();
+                    },
+                    "Register selected files"
+                }
+                if total() > 0 {
+                    progress {
+                        class: "progress progress-primary w-full",
+                        value: "{processed()}",
+                        max: "{total()}",
+                    }
+                    div { class: "text-xs text-gray-500",
+                        if is_registering() {
+                            "Hashed {processed()} / {total()}"
+                        } else {
+                            "{inserted()} inserted, {updated()} updated, {failures().len()} failed"
+                        }
+                    }
+                }
+                if !failures().is_empty() {
+                    ul { class: "mt-2 text-sm text-red-500",
+                        for (filepath, message) in failures() {
+                            li { class: "break-all", "{filepath}: {message}" }
                         }
                     }
                 }
             }
-            form { method: "dialog", class: "modal-backdrop", button { onclick: move |_| is_modal_open.toggle(), "close" } }
+            rehash::RehashReferenceImages { selected_reference_images, reference_image_page_resource }
+            bundle::ReferenceBundle { selected_reference_images, reference_image_page_resource }
+            library::ReferenceLibrary { query, selected_reference_images, reference_image_page_resource, is_registering }
         }
     }
 }
@@ -0,0 +1,94 @@
+use std::collections::HashSet;
+
+use crate::{backend, components::common, models::reference_image::ReferenceImagePage};
+use dioxus::prelude::*;
+use futures::StreamExt;
+
+#[component]
+pub fn RehashReferenceImages(
+    selected_reference_images: Signal<HashSet<i32>>,
+    reference_image_page_resource: Resource<Result<ReferenceImagePage, ServerFnError>>,
+) -> Element {
+    let mut is_rehashing = use_signal(|| false);
+    let mut processed = use_signal(|| 0usize);
+    let mut total = use_signal(|| 0usize);
+    let mut failures = use_signal(Vec::<(String, String)>::new);
+
+    // Every reference image is re-hashed when no id is given
+    let rehash = move |reference_image_ids: Vec<i32>| async move {
+        is_rehashing.set(true);
+        processed.set(0);
+        total.set(0);
+        failures.write().clear();
+
+        match backend::rehash_reference_images_stream(reference_image_ids).await {
+            Ok(stream) => {
+                let mut stream = stream.into_inner();
+                while let Some(Ok(progress)) = stream.next().await {
+                    processed.set(progress.processed);
+                    total.set(progress.total);
+                    if !progress.is_success {
+                        let message = progress.message.unwrap_or("Unknown error".to_string());
+                        failures.write().push((progress.filepath, message));
+                    }
+                }
+
+                if failures().is_empty() {
+                    common::show_toast(
+                        format!("Re-hashed {} reference images", total()),
+                        common::ToastType::Success,
+                    );
+                } else {
+                    common::show_toast(
+                        "Failed to re-hash some reference images",
+                        common::ToastType::Error,
+                    );
+                }
+            }
+            Err(e) => {
+                common::show_toast(e.to_string(), common::ToastType::Error);
+            }
+        }
+
+        is_rehashing.set(false);
+        reference_image_page_resource.restart();
+    };
+
+    rsx! {
+        div { class: "container p-4",
+            h3 { class: "mb-4", "Re-hash reference images" }
+            p { class: "text-xs text-gray-500 mb-2",
+                "Recompute the hashes of the registered reference images from their source files."
+            }
+            div { class: "flex flex-row gap-2 mb-2",
+                button {
+                    class: "btn btn-secondary flex-1",
+                    disabled: is_rehashing(),
+                    onclick: move |_| rehash(Vec::new()),
+                    "Re-hash all"
+                }
+                button {
+                    class: "btn btn-secondary btn-outline flex-1",
+                    disabled: is_rehashing() || selected_reference_images().is_empty(),
+                    onclick: move |_| rehash(selected_reference_images().into_iter().collect()),
+                    "Re-hash {selected_reference_images().len()} selected"
+                }
+            }
+            if total() > 0 {
+                progress {
+                    class: "progress progress-secondary w-full",
+                    value: "{processed()}",
+                    max: "{total()}",
+                }
+                div { class: "text-xs text-gray-500", "{processed()} / {total()}" }
+            }
+            if !failures().is_empty() {
+                ul { class: "mt-2 text-sm text-red-500",
+                    for (filepath, message) in failures() {
+                        li { class: "break-all", "{filepath}: {message}" }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::{
+    backend,
+    components::common,
+    models::{self, settings::ScanStrategy},
+};
+use dioxus::prelude::*;
+
+#[component]
+pub fn Settings() -> Element {
+    let mut is_saving = use_signal(|| false);
+    let mut settings = use_signal(models::settings::Settings::default);
+
+    let mut settings_resource = use_server_future(backend::get_settings)?;
+
+    // Edit a copy of the stored settings, which is replaced whenever they are reloaded
+    use_effect(move || {
+        if let Some(Ok(info)) = settings_resource() {
+            settings.set(info.settings);
+        }
+    });
+
+    rsx! {
+        div { class: "container p-4",
+            h3 { class: "mb-4", "Settings" }
+            if let Some(Ok(info)) = settings_resource() {
+                p { class: "text-xs text-gray-500 break-all", "Config file: {info.config_path}" }
+                p { class: "text-xs text-gray-500 break-all mb-4", "Database: {info.database_path}" }
+            }
+            fieldset { class: "fieldset",
+                label { class: "label text-sm", "Similarity threshold (%)" }
+                input {
+                    r#type: "number",
+                    class: "input input-sm w-32",
+                    min: "0",
+                    max: "100",
+                    value: "{settings().similarity_threshold}",
+                    oninput: move |e| {
+                        if let Ok(similarity_threshold) = e.value().parse() {
+                            settings.write().similarity_threshold = similarity_threshold;
+                        }
+                    },
+                }
+                label { class: "label text-sm", "Maximum number of results" }
+                input {
+                    r#type: "number",
+                    class: "input input-sm w-32",
+                    min: "1",
+                    value: "{settings().result_limit}",
+                    oninput: move |e| {
+                        if let Ok(result_limit) = e.value().parse() {
+                            settings.write().result_limit = result_limit;
+                        }
+                    },
+                }
+                label { class: "label text-sm", "Scan strategy" }
+                div { class: "flex flex-row items-center gap-3",
+                    select {
+                        class: "select select-sm w-80",
+                        onchange: move |e| {
+                            settings.write().scan_strategy = if e.value() == "all" { ScanStrategy::All } else { ScanStrategy::Sample };
+                        },
+                        option { value: "sample", selected: settings().scan_strategy == ScanStrategy::Sample, "{ScanStrategy::Sample}" }
+                        option { value: "all", selected: settings().scan_strategy == ScanStrategy::All, "{ScanStrategy::All}" }
+                    }
+                    if settings().scan_strategy == ScanStrategy::Sample {
+                        input {
+                            r#type: "number",
+                            class: "input input-sm w-20",
+                            min: "1",
+                            value: "{settings().sample_size}",
+                            oninput: move |e| {
+                                if let Ok(sample_size) = e.value().parse() {
+                                    settings.write().sample_size = sample_size;
+                                }
+                            },
+                        }
+                    }
+                }
+                label { class: "label text-sm", "Default delete method" }
+                select {
+                    class: "select select-sm w-80",
+                    onchange: move |e| {
+                        if let Some(method) = e.value().parse::<usize>().ok().and_then(|i| models::DeleteMethod::ALL.get(i)) {
+                            settings.write().delete_method = *method;
+                        }
+                    },
+                    for (i, method) in models::DeleteMethod::ALL.into_iter().enumerate() {
+                        if settings_resource().and_then(|r| r.ok()).is_some_and(|info| info.delete_methods.contains(&method)) {
+                            option { value: "{i}", selected: settings().delete_method == method, "{method}" }
+                        }
+                    }
+                }
+                label { class: "label text-sm", "Default quarantine directory" }
+                input {
+                    r#type: "text",
+                    class: "input input-sm w-full",
+                    value: "{settings().quarantine_directory.unwrap_or_default()}",
+                    oninput: move |e| {
+                        settings.write().quarantine_directory = Some(e.value()).filter(|d| !d.is_empty());
+                    },
+                }
+            }
+            p { class: "text-xs text-gray-500 mt-2",
+                "Environment variables and command line options take precedence over these settings. The database location is applied on the next start."
+            }
+            button {
+                class: "btn btn-primary w-full mt-4",
+                disabled: is_saving(),
+                onclick: move |_| async move {
+                    is_saving.set(true);
+
+                    match backend::save_settings(settings()).await {
+                        Ok(_) => {
+                            common::show_toast("Settings saved", common::ToastType::Success);
+                            settings_resource.restart();
+                        }
+                        Err(e) => {
+                            common::show_toast(e.to_string(), common::ToastType::Error);
+                        }
+                    }
+
+                    is_saving.set(false);
+                },
+                "Save"
+            }
+        }
+    }
+}
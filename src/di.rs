@@ -1,20 +1,44 @@
 use std::sync::Arc;
 
 use sea_orm::DatabaseConnection;
-use tokio::sync::OnceCell;
+use tokio::sync::{OnceCell, RwLock};
 
-use crate::repositories::reference_image_repository::ReferenceImageRepository;
+use crate::{
+    repositories::{
+        action_journal_repository::ActionJournalRepository,
+        quarantine_entry_repository::QuarantineEntryRepository,
+        reference_image_repository::ReferenceImageRepository,
+    },
+    search_session::SearchSession,
+    services::thumbnails::ThumbnailService,
+    settings,
+};
 
 pub struct Container {
     pub reference_image_repository: Arc<ReferenceImageRepository>,
+    pub quarantine_entry_repository: Arc<QuarantineEntryRepository>,
+    pub action_journal_repository: Arc<ActionJournalRepository>,
+    // Result of the last search, replaced on every search
+    pub search_session: Arc<RwLock<Option<SearchSession>>>,
+    pub thumbnail_service: Arc<ThumbnailService>,
 }
 
 impl Container {
     pub async fn new(db: &DatabaseConnection) -> Self {
         let reference_image_repository = Arc::new(ReferenceImageRepository::new(db.clone()));
+        let quarantine_entry_repository = Arc::new(QuarantineEntryRepository::new(db.clone()));
+        let action_journal_repository = Arc::new(ActionJournalRepository::new(db.clone()));
+        // Fall back to the temporary directory when there is no cache directory
+        let thumbnail_cache_directory = settings::thumbnail_cache_directory()
+            .unwrap_or_else(|_| std::env::temp_dir().join("imgdup").join("thumbnails"));
+        let thumbnail_service = Arc::new(ThumbnailService::new(thumbnail_cache_directory));
 
         Self {
             reference_image_repository,
+            quarantine_entry_repository,
+            action_journal_repository,
+            search_session: Arc::new(RwLock::new(None)),
+            thumbnail_service,
         }
     }
 }
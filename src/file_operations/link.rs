@@ -0,0 +1,144 @@
+use std::{
+    fs,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+
+use crate::models::LinkMethod;
+
+// Replace the file with a link to the target
+// The link is created next to the file and renamed over it, so the path never goes missing
+pub fn replace_with_link(path: &Path, target: &Path, method: LinkMethod) -> Result<()> {
+    let metadata = fs::symlink_metadata(path)
+        .with_context(|| format!("File not found: {}", path.display()))?;
+    if !metadata.is_file() {
+        bail!("Not a file: {}", path.display());
+    }
+    let target_metadata = fs::metadata(target)
+        .with_context(|| format!("Link target not found: {}", target.display()))?;
+    if !target_metadata.is_file() {
+        bail!("Link target is not a file: {}", target.display());
+    }
+    if metadata.dev() == target_metadata.dev() && metadata.ino() == target_metadata.ino() {
+        bail!("The file is already linked to {}", target.display());
+    }
+
+    // Hardlinks and reflinks can not cross filesystems
+    if matches!(method, LinkMethod::Hardlink | LinkMethod::Reflink)
+        && metadata.dev() != target_metadata.dev()
+    {
+        bail!(
+            "{method} requires the file and {} to be on the same filesystem",
+            target.display()
+        );
+    }
+
+    let temporary_path = temporary_path(path)?;
+    let result = match method {
+        LinkMethod::Hardlink => {
+            fs::hard_link(target, &temporary_path).context("Failed to create hardlink")
+        }
+        LinkMethod::Symlink => target
+            .canonicalize()
+            .context("Failed to resolve the link target")
+            .and_then(|target| {
+                std::os::unix::fs::symlink(target, &temporary_path)
+                    .context("Failed to create symlink")
+            }),
+        LinkMethod::Reflink => reflink(target, &temporary_path).and_then(|_| {
+            fs::set_permissions(&temporary_path, target_metadata.permissions())
+                .context("Failed to copy permissions")
+        }),
+    }
+    .and_then(|_| fs::rename(&temporary_path, path).context("Failed to replace the file"));
+
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temporary_path);
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+fn temporary_path(path: &Path) -> Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("Invalid file path: {}", path.display()))?
+        .to_string_lossy()
+        .to_string();
+
+    let mut counter = 1;
+    loop {
+        let temporary_path = path.with_file_name(format!(".{file_name}.imgdup-link.{counter}"));
+        if fs::symlink_metadata(&temporary_path).is_err() {
+            return Ok(temporary_path);
+        }
+        counter += 1;
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn reflink(target: &Path, destination: &Path) -> Result<()> {
+    use std::os::fd::AsRawFd;
+
+    // FICLONE from linux/fs.h
+    const FICLONE: libc::c_ulong = 0x4004_9409;
+
+    let source = fs::File::open(target)
+        .with_context(|| format!("Failed to open file: {}", target.display()))?;
+    let destination = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(destination)
+        .with_context(|| format!("Failed to create file: {}", destination.display()))?;
+    if unsafe { libc::ioctl(destination.as_raw_fd(), FICLONE as _, source.as_raw_fd()) } != 0 {
+        return Err(std::io::Error::last_os_error())
+            .context("Failed to create reflink, the filesystem may not support it");
+    }
+
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn reflink(_target: &Path, _destination: &Path) -> Result<()> {
+    bail!("Reflinks are not supported on this platform")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace_with_link() {
+        let dir = std::env::temp_dir().join(format!("imgdup-link-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let target = dir.join("original.png");
+        let hardlinked = dir.join("hardlinked.png");
+        let symlinked = dir.join("symlinked.png");
+        fs::write(&target, "original").unwrap();
+        fs::write(&hardlinked, "duplicate").unwrap();
+        fs::write(&symlinked, "duplicate").unwrap();
+
+        replace_with_link(&hardlinked, &target, LinkMethod::Hardlink).unwrap();
+        assert_eq!(fs::read_to_string(&hardlinked).unwrap(), "original");
+        assert_eq!(
+            fs::metadata(&hardlinked).unwrap().ino(),
+            fs::metadata(&target).unwrap().ino()
+        );
+        assert!(replace_with_link(&hardlinked, &target, LinkMethod::Hardlink).is_err());
+
+        replace_with_link(&symlinked, &target, LinkMethod::Symlink).unwrap();
+        assert!(fs::symlink_metadata(&symlinked)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_to_string(&symlinked).unwrap(), "original");
+
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,153 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::{
+    image_processing,
+    models::{DeleteMethod, FileFingerprint, LinkMethod},
+};
+
+#[cfg(unix)]
+pub mod link;
+pub mod quarantine;
+#[cfg(all(unix, not(target_os = "macos")))]
+pub mod trash;
+
+// Delete the file with the given method
+// Returns the new location of the file unless it was deleted permanently
+pub fn delete_file(
+    path: &Path,
+    method: DeleteMethod,
+    quarantine_directory: Option<&Path>,
+    root: &Path,
+) -> Result<Option<PathBuf>> {
+    match method {
+        DeleteMethod::Trash => move_to_trash(path).map(Some),
+        DeleteMethod::Quarantine => {
+            let quarantine_directory =
+                quarantine_directory.context("No quarantine directory selected")?;
+            quarantine::move_to_quarantine(path, quarantine_directory, root).map(Some)
+        }
+        DeleteMethod::Permanent => {
+            std::fs::remove_file(path)?;
+            Ok(None)
+        }
+    }
+}
+
+// Describe what `delete_file` would do with the file without touching it
+pub fn describe_delete(
+    path: &Path,
+    method: DeleteMethod,
+    quarantine_directory: Option<&Path>,
+    root: &Path,
+) -> Result<String> {
+    match method {
+        DeleteMethod::Trash if !method.is_supported() => {
+            anyhow::bail!("Moving files to the trash is not supported on this platform")
+        }
+        DeleteMethod::Trash => Ok("Move to the trash".to_string()),
+        DeleteMethod::Quarantine => {
+            let quarantine_directory =
+                quarantine_directory.context("No quarantine directory selected")?;
+            let destination = quarantine::quarantine_destination(path, quarantine_directory, root)?;
+            Ok(format!("Move to {}", destination.display()))
+        }
+        DeleteMethod::Permanent => Ok("Delete permanently".to_string()),
+    }
+}
+
+pub fn fingerprint(path: &Path) -> Result<FileFingerprint> {
+    let metadata =
+        std::fs::metadata(path).with_context(|| format!("File not found: {}", path.display()))?;
+    Ok(FileFingerprint {
+        size: metadata.len(),
+        modified_at: metadata.modified().ok().map(Into::into),
+        digest: image_processing::compute_digest(&path.to_string_lossy())?,
+    })
+}
+
+// Check that the file can be moved or removed, which requires write access to its directory
+pub fn check_writable(path: &Path) -> Result<()> {
+    let metadata =
+        std::fs::metadata(path).with_context(|| format!("File not found: {}", path.display()))?;
+    if !metadata.is_file() {
+        anyhow::bail!("Not a file: {}", path.display());
+    }
+
+    let parent = path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    if !is_writable_directory(parent) {
+        anyhow::bail!("Directory is not writable: {}", parent.display());
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn is_writable_directory(path: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+
+    let Ok(path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    unsafe { libc::access(path.as_ptr(), libc::W_OK) == 0 }
+}
+
+#[cfg(not(unix))]
+fn is_writable_directory(path: &Path) -> bool {
+    std::fs::metadata(path).is_ok_and(|m| !m.permissions().readonly())
+}
+
+// Move the file, falling back to copy and remove when the destination is on another filesystem
+pub fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    std::fs::copy(from, to)
+        .with_context(|| format!("Failed to move {} to {}", from.display(), to.display()))?;
+    if let Err(e) = std::fs::remove_file(from) {
+        let _ = std::fs::remove_file(to);
+        return Err(e).with_context(|| format!("Failed to remove {}", from.display()));
+    }
+
+    Ok(())
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn restore_from_trash(trash_path: &Path, original_path: &Path) -> Result<()> {
+    trash::restore_from_trash(trash_path, original_path)
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+pub fn restore_from_trash(_trash_path: &Path, _original_path: &Path) -> Result<()> {
+    anyhow::bail!("Restoring files from the trash is not supported on this platform")
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn move_to_trash(path: &Path) -> Result<PathBuf> {
+    trash::move_to_trash(path)
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+fn move_to_trash(_path: &Path) -> Result<PathBuf> {
+    anyhow::bail!("Moving files to the trash is not supported on this platform")
+}
+
+#[cfg(unix)]
+pub fn link_file(path: &Path, target: &Path, method: LinkMethod) -> Result<()> {
+    link::replace_with_link(path, target, method)
+}
+
+#[cfg(not(unix))]
+pub fn link_file(_path: &Path, _target: &Path, _method: LinkMethod) -> Result<()> {
+    anyhow::bail!("Linking files is not supported on this platform")
+}
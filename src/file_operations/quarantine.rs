@@ -0,0 +1,173 @@
+use std::{
+    fs::OpenOptions,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+
+use super::move_file;
+
+// Move the file into the quarantine directory, mirroring its path relative to the search root
+// Returns the path of the file in the quarantine directory
+pub fn move_to_quarantine(
+    path: &Path,
+    quarantine_directory: &Path,
+    root: &Path,
+) -> Result<PathBuf> {
+    let relative_path = relative_to_root(path, root);
+    if let Some(parent) = quarantine_directory.join(&relative_path).parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+
+    // Creating the destination exclusively reserves it, so a file quarantined
+    // at the same time from the same path can not be overwritten
+    let mut counter = 1;
+    let destination = loop {
+        let destination = destination_candidate(quarantine_directory, &relative_path, counter)?;
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&destination)
+        {
+            Ok(_) => break destination,
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => counter += 1,
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to create file: {}", destination.display()))
+            }
+        }
+    };
+
+    if let Err(e) = move_file(path, &destination) {
+        let _ = std::fs::remove_file(&destination);
+        return Err(e);
+    }
+    Ok(destination)
+}
+
+// Where `move_to_quarantine` would move the file now, without reserving it
+pub fn quarantine_destination(
+    path: &Path,
+    quarantine_directory: &Path,
+    root: &Path,
+) -> Result<PathBuf> {
+    let relative_path = relative_to_root(path, root);
+    let mut counter = 1;
+    loop {
+        let destination = destination_candidate(quarantine_directory, &relative_path, counter)?;
+        if !destination.exists() {
+            return Ok(destination);
+        }
+        counter += 1;
+    }
+}
+
+// Files quarantined earlier from the same path are never overwritten,
+// later ones get a counter appended to their name
+fn destination_candidate(
+    quarantine_directory: &Path,
+    relative_path: &Path,
+    counter: u32,
+) -> Result<PathBuf> {
+    if counter == 1 {
+        return Ok(quarantine_directory.join(relative_path));
+    }
+    let mut file_name = relative_path
+        .file_name()
+        .context("Invalid file path")?
+        .to_os_string();
+    file_name.push(format!(".{counter}"));
+    Ok(quarantine_directory.join(relative_path.with_file_name(file_name)))
+}
+
+pub fn restore_from_quarantine(quarantine_path: &Path, original_path: &Path) -> Result<()> {
+    if original_path.exists() {
+        bail!(
+            "A file already exists at the original location: {}",
+            original_path.display()
+        );
+    }
+
+    move_file(quarantine_path, original_path)
+}
+
+// Paths outside of the search root keep their full path without the root or prefix component
+// The root is canonical, so the directory of the file is resolved the same way before comparing
+fn relative_to_root(path: &Path, root: &Path) -> PathBuf {
+    let path = canonical_parent(path).unwrap_or_else(|| path.to_path_buf());
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    match path.strip_prefix(&root) {
+        Ok(relative_path) => relative_path.to_path_buf(),
+        Err(_) => path
+            .components()
+            .filter(|c| matches!(c, Component::Normal(_)))
+            .collect(),
+    }
+}
+
+// The file itself is not resolved, so a symlink is quarantined instead of its target
+fn canonical_parent(path: &Path) -> Option<PathBuf> {
+    let parent = path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    Some(parent.canonicalize().ok()?.join(path.file_name()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_to_root() {
+        assert_eq!(
+            relative_to_root(Path::new("/images/a/b.png"), Path::new("/images")),
+            PathBuf::from("a/b.png")
+        );
+        assert_eq!(
+            relative_to_root(Path::new("/other/a/b.png"), Path::new("/images")),
+            PathBuf::from("other/a/b.png")
+        );
+    }
+
+    #[test]
+    fn test_move_to_quarantine_and_restore() {
+        let dir = std::env::temp_dir().join(format!("imgdup-quarantine-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let root = dir.join("images");
+        let quarantine_directory = dir.join("quarantine");
+        let file = root.join("a/b.png");
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+
+        std::fs::write(&file, "first").unwrap();
+        let first = move_to_quarantine(&file, &quarantine_directory, &root).unwrap();
+        assert_eq!(first, quarantine_directory.join("a/b.png"));
+        assert!(!file.exists());
+
+        std::fs::write(&file, "second").unwrap();
+        let second = move_to_quarantine(&file, &quarantine_directory, &root).unwrap();
+        assert_eq!(second, quarantine_directory.join("a/b.png.2"));
+
+        restore_from_quarantine(&first, &file).unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "first");
+        assert!(restore_from_quarantine(&second, &file).is_err());
+
+        // The file is found under the root when it is reached through another path
+        #[cfg(unix)]
+        {
+            let link = dir.join("link");
+            std::os::unix::fs::symlink(&root, &link).unwrap();
+            assert_eq!(
+                relative_to_root(&link.join("a/b.png"), &root),
+                PathBuf::from("a/b.png")
+            );
+            assert_eq!(
+                relative_to_root(&root.join("a/../a/b.png"), &link),
+                PathBuf::from("a/b.png")
+            );
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
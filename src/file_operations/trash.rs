@@ -0,0 +1,272 @@
+// Move files to the trash following the FreeDesktop.org Trash specification
+// https://specifications.freedesktop.org/trash-spec/latest/
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    os::unix::{
+        ffi::OsStrExt,
+        fs::{DirBuilderExt, MetadataExt, PermissionsExt},
+    },
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+
+// Returns the path of the file in the trash
+pub fn move_to_trash(path: &Path) -> Result<PathBuf> {
+    let path = absolute_path(path)?;
+    let metadata = fs::symlink_metadata(&path)
+        .with_context(|| format!("File not found: {}", path.display()))?;
+
+    // Files on the same filesystem as the home trash go there,
+    // otherwise they go to the trash at the top directory of their mount point
+    let home_trash = home_trash_dir()?;
+    if let Some(home_trash_base) = home_trash.parent() {
+        if fs::metadata(home_trash_base).is_ok_and(|m| m.dev() == metadata.dev()) {
+            return trash_file_into(&home_trash, &path, &path);
+        }
+    }
+
+    let topdir = mount_point(&path, metadata.dev())?;
+    let relative_path = path.strip_prefix(&topdir).unwrap_or(&path).to_path_buf();
+    let trash = topdir_trash_dir(&topdir)?;
+    trash_file_into(&trash, &path, &relative_path)
+}
+
+// Move a trashed file back to its original location and remove its `.trashinfo` file
+pub fn restore_from_trash(trash_path: &Path, original_path: &Path) -> Result<()> {
+    if original_path.exists() {
+        bail!(
+            "A file already exists at the original location: {}",
+            original_path.display()
+        );
+    }
+
+    let name = trash_path
+        .file_name()
+        .with_context(|| format!("Invalid trash path: {}", trash_path.display()))?
+        .to_string_lossy()
+        .to_string();
+    let info_path = trash_path
+        .parent()
+        .and_then(|files_dir| files_dir.parent())
+        .with_context(|| format!("Invalid trash path: {}", trash_path.display()))?
+        .join("info")
+        .join(format!("{name}.trashinfo"));
+
+    super::move_file(trash_path, original_path)?;
+    let _ = fs::remove_file(info_path);
+
+    Ok(())
+}
+
+// Move the file into the given trash directory and write its `.trashinfo` file
+// `info_path` is the value recorded as `Path=`, either absolute or relative to the top directory
+fn trash_file_into(trash: &Path, path: &Path, info_path: &Path) -> Result<PathBuf> {
+    let files_dir = trash.join("files");
+    let info_dir = trash.join("info");
+    for dir in [&files_dir, &info_dir] {
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)
+            .with_context(|| format!("Failed to create trash directory: {}", dir.display()))?;
+    }
+
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("Invalid file path: {}", path.display()))?
+        .to_string_lossy()
+        .to_string();
+
+    // Creating the `.trashinfo` file exclusively reserves the name in the trash
+    // A file left in `files` without its `.trashinfo` still takes the name, so it is never overwritten
+    let mut counter = 1;
+    let (name, mut info_file) = loop {
+        let name = if counter == 1 {
+            file_name.clone()
+        } else {
+            format!("{file_name}.{counter}")
+        };
+        if fs::symlink_metadata(files_dir.join(&name)).is_ok() {
+            counter += 1;
+            continue;
+        }
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(info_dir.join(format!("{name}.trashinfo")))
+        {
+            Ok(f) => break (name, f),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => counter += 1,
+            Err(e) => return Err(e).context("Failed to create trash info file"),
+        }
+    };
+    let info_path_in_trash = info_dir.join(format!("{name}.trashinfo"));
+    let trash_path = files_dir.join(&name);
+
+    let info = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode_path(info_path),
+        chrono::Local::now().format("%Y-%m-%dT%H:%M:%S")
+    );
+    if let Err(e) = info_file.write_all(info.as_bytes()) {
+        let _ = fs::remove_file(&info_path_in_trash);
+        return Err(e).context("Failed to write trash info file");
+    }
+
+    if let Err(e) = fs::rename(path, &trash_path) {
+        let _ = fs::remove_file(&info_path_in_trash);
+        return Err(e).with_context(|| format!("Failed to move to trash: {}", path.display()));
+    }
+
+    Ok(trash_path)
+}
+
+fn absolute_path(path: &Path) -> Result<PathBuf> {
+    // The file itself is not canonicalized, so a symlink is trashed instead of its target
+    let parent = path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("Invalid file path: {}", path.display()))?;
+    Ok(parent
+        .canonicalize()
+        .with_context(|| format!("File not found: {}", path.display()))?
+        .join(file_name))
+}
+
+fn home_trash_dir() -> Result<PathBuf> {
+    let data_home = match std::env::var_os("XDG_DATA_HOME").filter(|v| !v.is_empty()) {
+        Some(v) => PathBuf::from(v),
+        None => {
+            let home = std::env::var_os("HOME").context("HOME is not set")?;
+            PathBuf::from(home).join(".local/share")
+        }
+    };
+    Ok(data_home.join("Trash"))
+}
+
+fn mount_point(path: &Path, dev: u64) -> Result<PathBuf> {
+    let mut topdir = path.to_path_buf();
+    while let Some(parent) = topdir.parent() {
+        let parent_dev = fs::metadata(parent)
+            .with_context(|| format!("Failed to read metadata: {}", parent.display()))?
+            .dev();
+        if parent_dev != dev {
+            break;
+        }
+        topdir = parent.to_path_buf();
+    }
+    Ok(topdir)
+}
+
+// Use `$topdir/.Trash/$uid` when the administrator has set up a shared trash,
+// otherwise `$topdir/.Trash-$uid`
+fn topdir_trash_dir(topdir: &Path) -> Result<PathBuf> {
+    let uid = unsafe { libc::getuid() };
+
+    let shared_trash = topdir.join(".Trash");
+    if let Ok(metadata) = fs::symlink_metadata(&shared_trash) {
+        let is_sticky = metadata.permissions().mode() & 0o1000 != 0;
+        if metadata.is_dir() && !metadata.file_type().is_symlink() && is_sticky {
+            let user_trash = shared_trash.join(uid.to_string());
+            if fs::DirBuilder::new()
+                .recursive(true)
+                .mode(0o700)
+                .create(&user_trash)
+                .is_ok()
+            {
+                return Ok(user_trash);
+            }
+        }
+    }
+
+    let user_trash = topdir.join(format!(".Trash-{uid}"));
+    match fs::symlink_metadata(&user_trash) {
+        Ok(metadata) if metadata.is_dir() && metadata.uid() == uid => Ok(user_trash),
+        Ok(_) => bail!("Trash directory is not usable: {}", user_trash.display()),
+        Err(_) => {
+            fs::DirBuilder::new()
+                .mode(0o700)
+                .create(&user_trash)
+                .with_context(|| {
+                    format!("Failed to create trash directory: {}", user_trash.display())
+                })?;
+            Ok(user_trash)
+        }
+    }
+}
+
+// Percent-encode the path as required for the `Path=` key
+fn encode_path(path: &Path) -> String {
+    let mut encoded = String::new();
+    for &b in path.as_os_str().as_bytes() {
+        if b.is_ascii_alphanumeric() || b"/-_.!~*'()".contains(&b) {
+            encoded.push(b as char);
+        } else {
+            encoded.push_str(&format!("%{b:02X}"));
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("imgdup-trash-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_encode_path() {
+        assert_eq!(
+            encode_path(Path::new("/home/user/O'Brien's photos/a b%.png")),
+            "/home/user/O'Brien's%20photos/a%20b%25.png"
+        );
+    }
+
+    #[test]
+    fn test_trash_file_into() {
+        let dir = temp_dir("move");
+        let trash = dir.join("Trash");
+        let file = dir.join("test.png");
+
+        for _ in 0..2 {
+            fs::write(&file, "test").unwrap();
+            trash_file_into(&trash, &file, &file).unwrap();
+        }
+
+        assert!(!file.exists());
+        assert!(trash.join("files/test.png").exists());
+        assert!(trash.join("files/test.png.2").exists());
+
+        let info = fs::read_to_string(trash.join("info/test.png.2.trashinfo")).unwrap();
+        assert!(info.starts_with(&format!("[Trash Info]\nPath={}\n", encode_path(&file))));
+        assert!(info.contains("DeletionDate="));
+
+        restore_from_trash(&trash.join("files/test.png.2"), &file).unwrap();
+        assert!(file.exists());
+        assert!(!trash.join("files/test.png.2").exists());
+        assert!(!trash.join("info/test.png.2.trashinfo").exists());
+        assert!(restore_from_trash(&trash.join("files/test.png"), &file).is_err());
+
+        // An orphan file without its `.trashinfo` keeps its name
+        fs::remove_file(trash.join("info/test.png.trashinfo")).unwrap();
+        let trash_path = trash_file_into(&trash, &file, &file).unwrap();
+        assert_eq!(trash_path, trash.join("files/test.png.2"));
+        assert_eq!(
+            fs::read_to_string(trash.join("files/test.png")).unwrap(),
+            "test"
+        );
+        assert!(!trash.join("info/test.png.trashinfo").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
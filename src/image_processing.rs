@@ -1,14 +1,117 @@
 use anyhow::{Context, Result};
 use img_hash::{HasherConfig, ImageHash};
+use sha2::{Digest, Sha256};
+
+// Version of the hasher configuration below
+// Bump this whenever the hash algorithm, size or preprocessing changes,
+// hashes with different versions can not be compared with each other
+pub const HASH_VERSION: i32 = 1;
+pub const HASH_ALGORITHM: &str = "gradient";
+pub const HASH_WIDTH: u32 = 8;
+pub const HASH_HEIGHT: u32 = 8;
 
 pub fn compute_hash(path: &str) -> Result<ImageHash> {
     let img = image::open(path).context("Failed to open image")?;
-    let hasher = HasherConfig::new().hash_size(8, 8).to_hasher();
+    let hasher = HasherConfig::new()
+        .hash_size(HASH_WIDTH, HASH_HEIGHT)
+        .to_hasher();
     Ok(hasher.hash_image(&img))
 }
 
 pub fn calculate_similarity(h1: &ImageHash, h2: &ImageHash) -> u32 {
     let dist = h1.dist(h2);
-    let similarity = 100 - dist;
-    similarity
+    100 - dist
+}
+
+pub fn is_current_hash_version(hash_version: i32) -> bool {
+    hash_version == HASH_VERSION
+}
+
+// Compute the SHA-256 digest of the file content as a lowercase hex string
+pub fn compute_digest(path: &str) -> Result<String> {
+    let mut file =
+        std::fs::File::open(path).with_context(|| format!("Failed to open file: {path}"))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)
+        .with_context(|| format!("Failed to read file: {path}"))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+pub fn image_dimensions(path: &str) -> Result<(u32, u32)> {
+    image::image_dimensions(path).context("Failed to read image dimensions")
+}
+
+// Encode a JPEG thumbnail which fits in a `size` x `size` box
+pub fn encode_thumbnail(path: &str, size: u32) -> Result<Vec<u8>> {
+    let img = image::open(path).context("Failed to open image")?;
+    let mut buf = Vec::new();
+    // JPEG has no alpha channel
+    image::DynamicImage::ImageRgb8(img.thumbnail(size, size).to_rgb8())
+        .write_to(&mut buf, image::ImageOutputFormat::Jpeg(85))
+        .context("Failed to encode thumbnail")?;
+    Ok(buf)
+}
+
+pub fn image_format(path: &str) -> Result<String> {
+    let format = image::io::Reader::open(path)
+        .context("Failed to open image")?
+        .with_guessed_format()
+        .context("Failed to read image")?
+        .format()
+        .context("Unknown image format")?;
+    Ok(format!("{format:?}").to_uppercase())
+}
+
+// Read the date the picture was taken and the camera from the EXIF data
+pub fn read_exif(path: &str) -> (Option<String>, Option<String>) {
+    let Ok(file) = std::fs::File::open(path) else {
+        return (None, None);
+    };
+    let Ok(exif) = exif::Reader::new().read_from_container(&mut std::io::BufReader::new(file))
+    else {
+        return (None, None);
+    };
+    let field = |tag| match &exif.get_field(tag, exif::In::PRIMARY)?.value {
+        exif::Value::Ascii(values) => values
+            .first()
+            .map(|v| String::from_utf8_lossy(v).trim().to_string())
+            .filter(|v| !v.is_empty()),
+        _ => None,
+    };
+
+    let taken_at = field(exif::Tag::DateTimeOriginal).or_else(|| field(exif::Tag::DateTime));
+    let camera = match (field(exif::Tag::Make), field(exif::Tag::Model)) {
+        // Most models already start with the make
+        (Some(make), Some(model)) if model.starts_with(&make) => Some(model),
+        (Some(make), Some(model)) => Some(format!("{make} {model}")),
+        (make, model) => make.or(model),
+    };
+    (taken_at, camera)
+}
+
+// Encode a PNG of the per-pixel difference of the images, amplified to be visible
+// The reference is scaled to the thumbnail of the image, which fits in a `size` x `size` box
+pub fn encode_difference(path: &str, reference_path: &str, size: u32) -> Result<Vec<u8>> {
+    let img = image::open(path)
+        .context("Failed to open image")?
+        .thumbnail(size, size)
+        .to_rgb8();
+    let reference = image::open(reference_path)
+        .context("Failed to open reference image")?
+        .resize_exact(
+            img.width(),
+            img.height(),
+            image::imageops::FilterType::Triangle,
+        )
+        .to_rgb8();
+
+    let difference = image::RgbImage::from_fn(img.width(), img.height(), |x, y| {
+        let (a, b) = (img.get_pixel(x, y), reference.get_pixel(x, y));
+        image::Rgb([0, 1, 2].map(|i| a[i].abs_diff(b[i]).saturating_mul(4)))
+    });
+    let mut buf = Vec::new();
+    image::DynamicImage::ImageRgb8(difference)
+        .write_to(&mut buf, image::ImageOutputFormat::Png)
+        .context("Failed to encode difference")?;
+    Ok(buf)
 }
@@ -1,17 +1,86 @@
+#![cfg_attr(not(feature = "server"), allow(dead_code))]
+
 use dioxus::prelude::*;
 
+// Modules shared with the client only provide their types there
+#[cfg(feature = "server")]
 mod adapter;
 mod backend;
+mod bundle;
+#[cfg(feature = "server")]
+mod cli;
 mod components;
+#[cfg(feature = "server")]
 mod di;
+#[cfg(feature = "server")]
+mod file_operations;
 mod image_processing;
 mod models;
+mod report;
+#[cfg(feature = "server")]
 mod repositories;
+mod result_filter;
+#[cfg(feature = "server")]
+mod search_session;
+mod selection_policy;
+#[cfg(feature = "server")]
+mod services;
+#[cfg(feature = "server")]
+mod settings;
+#[cfg(feature = "server")]
+mod thumbnail_route;
 mod utils;
 
 const FAVICON: Asset = asset!("/assets/favicon.ico");
 const TAILWIND_CSS: Asset = asset!("/assets/tailwind.css");
 
+#[cfg(feature = "server")]
+fn main() {
+    let cli = cli::Cli::parse_args();
+
+    let mut overrides = match settings::Overrides::from_env() {
+        Ok(overrides) => overrides,
+        Err(e) => {
+            eprintln!("Error: {e:#}");
+            std::process::exit(cli::EXIT_ERROR);
+        }
+    };
+    cli.options.apply(&mut overrides);
+    settings::init(overrides);
+
+    // Migrate the database before anything else, so schema errors stop the startup
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to start the runtime");
+    if let Err(e) = runtime.block_on(adapter::sqlite::check_database()) {
+        eprintln!("Error: {e:#}");
+        std::process::exit(cli::EXIT_ERROR);
+    }
+
+    // Commands run headless, and the logger is left out so it does not mix with their output
+    if let Some(command) = cli.command {
+        let code = runtime.block_on(cli::run(command));
+        std::process::exit(code);
+    }
+
+    dioxus::logger::init(dioxus::logger::tracing::Level::DEBUG)
+        .expect("Failed to initialize logger");
+
+    // The server is started by hand to add the thumbnail route next to the server functions
+    runtime.block_on(async {
+        let router = thumbnail_route::router()
+            .serve_dioxus_application(ServeConfigBuilder::new(), App)
+            .into_make_service();
+        let address = dioxus::cli_config::fullstack_address_or_localhost();
+        let listener = tokio::net::TcpListener::bind(address)
+            .await
+            .expect("Failed to bind the server address");
+        axum::serve(listener, router)
+            .await
+            .expect("Failed to run the server");
+    });
+}
+
+// The desktop and web clients call the server functions of the server build
+#[cfg(not(feature = "server"))]
 fn main() {
     dioxus::logger::init(dioxus::logger::tracing::Level::DEBUG)
         .expect("Failed to initialize logger");
@@ -21,10 +90,18 @@ fn main() {
 
 #[component]
 fn App() -> Element {
+    // Local images are loaded through the thumbnail route
+    let thumbnail_token = use_server_future(backend::get_thumbnail_token)?;
+    use_context_provider(|| components::common::ThumbnailToken(thumbnail_token));
+    // Search results are kept above the router, so they survive switching pages
+    use_context_provider(components::home::SearchState::default);
+    use_context_provider(components::reference_images::ReferenceImagesState::default);
+
     rsx! {
         document::Link { rel: "icon", href: FAVICON }
         document::Link { rel: "stylesheet", href: TAILWIND_CSS }
 
-        components::home::Home {}
+        Router::<components::Route> {}
+        components::common::ToastContainer {}
     }
 }
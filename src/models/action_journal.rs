@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+use super::DeleteMethod;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum FileOperation {
+    Trash,
+    Quarantine,
+    Delete,
+    Restore,
+    // An undo which could not move the file back, so the next undo goes on to older batches
+    RestoreFailed,
+    Link,
+}
+
+impl FileOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileOperation::Trash => "trash",
+            FileOperation::Quarantine => "quarantine",
+            FileOperation::Delete => "delete",
+            FileOperation::Restore => "restore",
+            FileOperation::RestoreFailed => "restore_failed",
+            FileOperation::Link => "link",
+        }
+    }
+
+    pub fn parse(operation: &str) -> Option<Self> {
+        match operation {
+            "trash" => Some(FileOperation::Trash),
+            "quarantine" => Some(FileOperation::Quarantine),
+            "delete" => Some(FileOperation::Delete),
+            "restore" => Some(FileOperation::Restore),
+            "restore_failed" => Some(FileOperation::RestoreFailed),
+            "link" => Some(FileOperation::Link),
+            _ => None,
+        }
+    }
+}
+
+impl From<DeleteMethod> for FileOperation {
+    fn from(method: DeleteMethod) -> Self {
+        match method {
+            DeleteMethod::Trash => FileOperation::Trash,
+            DeleteMethod::Quarantine => FileOperation::Quarantine,
+            DeleteMethod::Permanent => FileOperation::Delete,
+        }
+    }
+}
+
+pub struct ActionJournalInput {
+    pub batch_id: String,
+    pub operation: FileOperation,
+    pub source: String,
+    pub destination: Option<String>,
+    pub digest_before: Option<String>,
+    pub digest_after: Option<String>,
+    pub reverts_batch_id: Option<String>,
+    // Entry which a restore reversed, or tried to
+    pub reverts_entry_id: Option<i32>,
+}
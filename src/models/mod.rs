@@ -1,4 +1,7 @@
+pub mod action_journal;
+pub mod quarantine_entry;
 pub mod reference_image;
+pub mod settings;
 
 use serde::{Deserialize, Serialize};
 
@@ -6,6 +9,169 @@ use serde::{Deserialize, Serialize};
 pub struct SimilarImage {
     pub filepath: String,
     pub similarity: u32,
+    // Path of the reference image with the highest similarity
+    pub matched_reference: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
     pub is_deleted: bool,
     pub error_message: Option<String>,
+    // State of the file at the time of the search
+    pub fingerprint: Option<FileFingerprint>,
+}
+
+impl SimilarImage {
+    // Number of pixels, 0 when the dimensions are unknown
+    pub fn resolution(&self) -> u64 {
+        self.width.unwrap_or_default() as u64 * self.height.unwrap_or_default() as u64
+    }
+
+    // File size at the time of the search, 0 when it is unknown
+    pub fn size(&self) -> u64 {
+        self.fingerprint
+            .as_ref()
+            .map(|f| f.size)
+            .unwrap_or_default()
+    }
+}
+
+// Metadata shown in the comparison view
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ImageDetails {
+    pub filepath: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub size: Option<u64>,
+    pub format: Option<String>,
+    // EXIF date and time the picture was taken
+    pub taken_at: Option<String>,
+    // EXIF make and model of the camera
+    pub camera: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ImageComparison {
+    pub candidate: ImageDetails,
+    pub reference: Option<ImageDetails>,
+    // Number of differing bits between the perceptual hashes
+    pub hash_distance: Option<u32>,
+}
+
+// Result of a search as written by `imgdup search --json` or exported from the UI
+// It can be passed back to `imgdup delete --from-results`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SearchReport {
+    pub directories: Vec<String>,
+    pub similar_images: Vec<SimilarImage>,
+    // Images selected for deletion in the UI, every image when `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub planned_deletions: Option<Vec<String>>,
+}
+
+impl SearchReport {
+    pub fn is_planned_deletion(&self, filepath: &str) -> bool {
+        self.planned_deletions
+            .as_ref()
+            .is_none_or(|p| p.iter().any(|planned| planned == filepath))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FileFingerprint {
+    pub size: u64,
+    pub modified_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub digest: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DryRunReport {
+    pub image_id: u32,
+    // The file which was checked, and which is deleted once the user confirms
+    pub filepath: String,
+    pub fingerprint: Option<FileFingerprint>,
+    pub is_ok: bool,
+    // What would happen to the file
+    pub action: String,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum DeleteMethod {
+    Trash,
+    Quarantine,
+    Permanent,
+}
+
+impl DeleteMethod {
+    pub const ALL: [DeleteMethod; 3] = [
+        DeleteMethod::Trash,
+        DeleteMethod::Quarantine,
+        DeleteMethod::Permanent,
+    ];
+
+    // Only the FreeDesktop trash is implemented
+    // This has to be checked on the server, which is where the files are
+    pub fn is_supported(&self) -> bool {
+        match self {
+            DeleteMethod::Trash => cfg!(all(unix, not(target_os = "macos"))),
+            DeleteMethod::Quarantine | DeleteMethod::Permanent => true,
+        }
+    }
+}
+
+// The trash is the default wherever it is supported
+impl Default for DeleteMethod {
+    fn default() -> Self {
+        if DeleteMethod::Trash.is_supported() {
+            DeleteMethod::Trash
+        } else {
+            DeleteMethod::Permanent
+        }
+    }
+}
+
+impl std::fmt::Display for DeleteMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeleteMethod::Trash => write!(f, "Move to trash"),
+            DeleteMethod::Quarantine => write!(f, "Move to quarantine"),
+            DeleteMethod::Permanent => write!(f, "Delete permanently"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum LinkMethod {
+    #[default]
+    Hardlink,
+    Symlink,
+    Reflink,
+}
+
+impl std::fmt::Display for LinkMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LinkMethod::Hardlink => write!(f, "Hardlink"),
+            LinkMethod::Symlink => write!(f, "Symlink"),
+            LinkMethod::Reflink => write!(f, "Reflink"),
+        }
+    }
+}
+
+// What a duplicate is linked to
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum LinkTarget {
+    // The reference image it matched
+    #[default]
+    Reference,
+    // The image kept in the same match group
+    Keeper,
+}
+
+impl std::fmt::Display for LinkTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LinkTarget::Reference => write!(f, "Matched reference"),
+            LinkTarget::Keeper => write!(f, "Kept image"),
+        }
+    }
 }
@@ -0,0 +1,5 @@
+pub struct QuarantineEntryInput {
+    pub original_path: String,
+    pub quarantine_path: String,
+    pub digest: String,
+}
@@ -1,4 +1,132 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Default)]
 pub struct ReferenceImageInput {
     pub filepath: String,
     pub hash: Vec<u8>,
+    pub hash_version: i32,
+    pub digest: Option<String>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub collection: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct UpsertSummary {
+    pub inserted: Vec<String>,
+    pub updated: Vec<String>,
+}
+
+// File which could not be registered as a reference image
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RejectedFile {
+    pub filepath: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum ReferenceImageSortKey {
+    #[default]
+    CreatedAt,
+    UpdatedAt,
+    Path,
+    Collection,
+}
+
+impl ReferenceImageSortKey {
+    pub const ALL: [ReferenceImageSortKey; 4] = [
+        ReferenceImageSortKey::CreatedAt,
+        ReferenceImageSortKey::UpdatedAt,
+        ReferenceImageSortKey::Path,
+        ReferenceImageSortKey::Collection,
+    ];
+}
+
+impl std::fmt::Display for ReferenceImageSortKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReferenceImageSortKey::CreatedAt => write!(f, "Created"),
+            ReferenceImageSortKey::UpdatedAt => write!(f, "Updated"),
+            ReferenceImageSortKey::Path => write!(f, "Path"),
+            ReferenceImageSortKey::Collection => write!(f, "Collection"),
+        }
+    }
+}
+
+// Whether the source file of a reference image still exists
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum FileStatus {
+    #[default]
+    Any,
+    Present,
+    Missing,
+}
+
+impl std::fmt::Display for FileStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileStatus::Any => write!(f, "Any file status"),
+            FileStatus::Present => write!(f, "File present"),
+            FileStatus::Missing => write!(f, "File missing"),
+        }
+    }
+}
+
+// Largest page which is served at once, whatever the client asks for
+pub const MAX_PAGE_SIZE: u64 = 200;
+
+// Page of the reference image library
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReferenceImageQuery {
+    // Case-insensitive substring of the path
+    pub path: String,
+    pub collection: Option<String>,
+    // Registration dates, both ends included
+    pub created_from: Option<chrono::NaiveDate>,
+    pub created_to: Option<chrono::NaiveDate>,
+    pub file_status: FileStatus,
+    pub sort_key: ReferenceImageSortKey,
+    pub is_descending: bool,
+    pub page: u64,
+    pub page_size: u64,
+}
+
+impl Default for ReferenceImageQuery {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            collection: None,
+            created_from: None,
+            created_to: None,
+            file_status: FileStatus::Any,
+            sort_key: ReferenceImageSortKey::CreatedAt,
+            is_descending: true,
+            page: 0,
+            page_size: 24,
+        }
+    }
+}
+
+impl ReferenceImageQuery {
+    pub fn limit(&self) -> u64 {
+        self.page_size.clamp(1, MAX_PAGE_SIZE)
+    }
+
+    // SQLite binds the offset as a signed integer
+    pub fn offset(&self) -> u64 {
+        self.page.saturating_mul(self.limit()).min(i64::MAX as u64)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReferenceImageEntry {
+    pub reference_image: entity::reference_image::Model,
+    pub is_missing: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ReferenceImagePage {
+    pub entries: Vec<ReferenceImageEntry>,
+    // Number of reference images matching the query on all pages
+    pub total: u64,
 }
@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+
+use super::DeleteMethod;
+
+// Persisted application settings
+// Missing keys in the config file fall back to the defaults
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    // Defaults to `imgdup/db.sqlite` under the data directory
+    pub database_path: Option<String>,
+    pub similarity_threshold: u32,
+    pub result_limit: usize,
+    pub scan_strategy: ScanStrategy,
+    // Number of images taken from the start and the end of each directory with `ScanStrategy::Sample`
+    pub sample_size: usize,
+    pub delete_method: DeleteMethod,
+    pub quarantine_directory: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            database_path: None,
+            similarity_threshold: 90,
+            result_limit: 10,
+            scan_strategy: ScanStrategy::Sample,
+            sample_size: 5,
+            delete_method: DeleteMethod::default(),
+            quarantine_directory: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanStrategy {
+    // Only the first and last images of each directory
+    Sample,
+    All,
+}
+
+impl std::fmt::Display for ScanStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScanStrategy::Sample => write!(f, "First and last images of each directory"),
+            ScanStrategy::All => write!(f, "All images"),
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{SearchReport, SimilarImage};
+
+// Exported search results
+// The JSON file is a `SearchReport`, so it can be passed to `imgdup delete --from-results`
+// on another machine. The HTML report embeds the thumbnails and needs no other files
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ReportFormat {
+    Csv,
+    Json,
+    Html,
+}
+
+impl ReportFormat {
+    pub const ALL: [ReportFormat; 3] = [ReportFormat::Csv, ReportFormat::Json, ReportFormat::Html];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Csv => "csv",
+            ReportFormat::Json => "json",
+            ReportFormat::Html => "html",
+        }
+    }
+}
+
+impl std::fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReportFormat::Csv => write!(f, "CSV"),
+            ReportFormat::Json => write!(f, "JSON"),
+            ReportFormat::Html => write!(f, "HTML"),
+        }
+    }
+}
+
+// What was done or is planned for an image
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResultAction {
+    Keep,
+    Delete,
+    Deleted,
+    Failed,
+}
+
+impl ResultAction {
+    pub fn of(report: &SearchReport, similar_image: &SimilarImage) -> Self {
+        if similar_image.is_deleted {
+            ResultAction::Deleted
+        } else if similar_image.error_message.is_some() {
+            ResultAction::Failed
+        } else if report.is_planned_deletion(&similar_image.filepath) {
+            ResultAction::Delete
+        } else {
+            ResultAction::Keep
+        }
+    }
+}
+
+impl std::fmt::Display for ResultAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResultAction::Keep => write!(f, "keep"),
+            ResultAction::Delete => write!(f, "delete"),
+            ResultAction::Deleted => write!(f, "deleted"),
+            ResultAction::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+const COLUMNS: [&str; 8] = [
+    "candidate",
+    "matched_reference",
+    "similarity",
+    "size",
+    "width",
+    "height",
+    "action",
+    "error",
+];
+
+// `thumbnails` holds JPEG thumbnails by path, and is only used by the HTML report
+pub fn render(
+    report: &SearchReport,
+    format: ReportFormat,
+    thumbnails: &HashMap<String, Vec<u8>>,
+) -> Result<Vec<u8>> {
+    match format {
+        ReportFormat::Csv => render_csv(report),
+        ReportFormat::Json => {
+            serde_json::to_vec_pretty(report).context("Failed to encode the search results")
+        }
+        ReportFormat::Html => Ok(render_html(report, thumbnails).into_bytes()),
+    }
+}
+
+fn row(report: &SearchReport, similar_image: &SimilarImage) -> [String; 8] {
+    let optional = |value: Option<u32>| value.map(|v| v.to_string()).unwrap_or_default();
+    [
+        similar_image.filepath.clone(),
+        similar_image.matched_reference.clone().unwrap_or_default(),
+        similar_image.similarity.to_string(),
+        similar_image
+            .fingerprint
+            .as_ref()
+            .map(|f| f.size.to_string())
+            .unwrap_or_default(),
+        optional(similar_image.width),
+        optional(similar_image.height),
+        ResultAction::of(report, similar_image).to_string(),
+        similar_image.error_message.clone().unwrap_or_default(),
+    ]
+}
+
+fn render_csv(report: &SearchReport) -> Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(COLUMNS)?;
+    for similar_image in &report.similar_images {
+        writer.write_record(row(report, similar_image))?;
+    }
+    writer
+        .into_inner()
+        .context("Failed to encode the search results")
+}
+
+fn render_html(report: &SearchReport, thumbnails: &HashMap<String, Vec<u8>>) -> String {
+    let thumbnail = |path: &str| {
+        thumbnails
+            .get(path)
+            .map(|thumbnail| {
+                format!(
+                    r#"<img src="data:image/jpeg;base64,{}" alt="">"#,
+                    base64::engine::general_purpose::STANDARD.encode(thumbnail)
+                )
+            })
+            .unwrap_or_default()
+    };
+
+    let mut rows = String::new();
+    for similar_image in &report.similar_images {
+        let [candidate, matched_reference, similarity, size, width, height, action, error] =
+            row(report, similar_image).map(|value| escape_html(&value));
+        let dimensions = if width.is_empty() || height.is_empty() {
+            String::new()
+        } else {
+            format!("{width}x{height}")
+        };
+        rows.push_str(&format!(
+            "<tr class=\"{action}\"><td>{}</td><td>{candidate}</td><td>{}</td><td>{matched_reference}</td>\
+             <td>{similarity}%</td><td>{size}</td><td>{dimensions}</td><td>{action}</td><td>{error}</td></tr>\n",
+            thumbnail(&similar_image.filepath),
+            similar_image
+                .matched_reference
+                .as_deref()
+                .map(thumbnail)
+                .unwrap_or_default(),
+        ));
+    }
+
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>imgdup report</title>
+<style>
+body {{ font-family: sans-serif; font-size: 14px; }}
+table {{ border-collapse: collapse; }}
+th, td {{ border: 1px solid #ddd; padding: 4px 8px; text-align: left; word-break: break-all; }}
+img {{ max-width: 128px; max-height: 128px; }}
+tr.deleted {{ color: #888; }}
+tr.failed {{ color: #c00; }}
+</style>
+</head>
+<body>
+<h1>imgdup report</h1>
+<p>Directories: {}</p>
+<p>{} similar images</p>
+<table>
+<tr><th></th><th>Candidate</th><th></th><th>Matched reference</th><th>Similarity</th><th>Size (bytes)</th><th>Dimensions</th><th>Action</th><th>Error</th></tr>
+{rows}</table>
+</body>
+</html>
+"#,
+        escape_html(&report.directories.join(", ")),
+        report.similar_images.len(),
+    )
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::FileFingerprint;
+
+    fn report() -> SearchReport {
+        let similar_image = |filepath: &str| SimilarImage {
+            filepath: filepath.to_string(),
+            similarity: 97,
+            matched_reference: Some("/refs/a.png".to_string()),
+            width: Some(640),
+            height: Some(480),
+            is_deleted: false,
+            error_message: None,
+            fingerprint: Some(FileFingerprint {
+                size: 1234,
+                modified_at: None,
+                digest: "digest".to_string(),
+            }),
+        };
+        SearchReport {
+            directories: vec!["/images".to_string()],
+            similar_images: vec![
+                similar_image("/images/O'Brien, \"copy\".png"),
+                similar_image("/images/<b>.png"),
+                SimilarImage {
+                    error_message: Some("Permission denied".to_string()),
+                    ..similar_image("/images/c.png")
+                },
+            ],
+            planned_deletions: Some(vec!["/images/<b>.png".to_string()]),
+        }
+    }
+
+    #[test]
+    fn test_render_csv() {
+        let csv = render(&report(), ReportFormat::Csv, &HashMap::new()).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[0],
+            "candidate,matched_reference,similarity,size,width,height,action,error"
+        );
+        assert_eq!(
+            lines[1],
+            r#""/images/O'Brien, ""copy"".png",/refs/a.png,97,1234,640,480,keep,"#
+        );
+        assert_eq!(
+            lines[2],
+            "/images/<b>.png,/refs/a.png,97,1234,640,480,delete,"
+        );
+        assert_eq!(
+            lines[3],
+            "/images/c.png,/refs/a.png,97,1234,640,480,failed,Permission denied"
+        );
+    }
+
+    #[test]
+    fn test_render_json() {
+        let report = report();
+        let json = render(&report, ReportFormat::Json, &HashMap::new()).unwrap();
+        let imported: SearchReport = serde_json::from_slice(&json).unwrap();
+        assert_eq!(imported, report);
+    }
+
+    #[test]
+    fn test_render_html() {
+        let thumbnails = HashMap::from([("/images/<b>.png".to_string(), b"jpeg".to_vec())]);
+        let html = render(&report(), ReportFormat::Html, &thumbnails).unwrap();
+        let html = String::from_utf8(html).unwrap();
+        assert!(html.contains("/images/&lt;b&gt;.png"));
+        assert!(html.contains("O&#39;Brien, &quot;copy&quot;.png"));
+        assert!(html.contains(r#"<img src="data:image/jpeg;base64,anBlZw==" alt="">"#));
+        assert!(html.contains("<td>640x480</td><td>delete</td>"));
+    }
+}
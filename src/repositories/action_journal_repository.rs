@@ -0,0 +1,342 @@
+use entity::action_journal::ActiveModel as ActionJournalActiveModel;
+use entity::action_journal::Column as ActionJournalColumn;
+use entity::action_journal::Entity as ActionJournalEntity;
+use entity::action_journal::Model as ActionJournalModel;
+use sea_orm::sea_query::Query;
+use sea_orm::ActiveValue::Set;
+use sea_orm::ColumnTrait;
+use sea_orm::QueryFilter;
+use sea_orm::QueryOrder;
+use sea_orm::QuerySelect;
+use sea_orm::Select;
+use sea_orm::TransactionTrait;
+use sea_orm::{ConnectionTrait, DatabaseConnection, EntityTrait};
+
+use crate::models::{self, action_journal::FileOperation};
+
+// The journal is append-only, so there are no update or delete methods
+pub struct ActionJournalRepository {
+    db: DatabaseConnection,
+}
+
+impl ActionJournalRepository {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    pub async fn find_recent(&self, limit: u64) -> Result<Vec<ActionJournalModel>, sea_orm::DbErr> {
+        ActionJournalEntity::find()
+            .order_by_desc(ActionJournalColumn::Id)
+            .limit(limit)
+            .all(&self.db)
+            .await
+    }
+
+    // Find the most recent batch of deletions which was not undone yet
+    // Irreversible batches are returned too, so the undo can report their files,
+    // and a batch counts as undone once every entry was tried, whether it was restored or not
+    pub async fn find_last_undoable_batch_id(&self) -> Result<Option<String>, sea_orm::DbErr> {
+        deletions()
+            .filter(
+                ActionJournalColumn::Id.not_in_subquery(reverting_entry_ids(&[
+                    FileOperation::Restore,
+                    FileOperation::RestoreFailed,
+                ])),
+            )
+            .select_only()
+            .column(ActionJournalColumn::BatchId)
+            .order_by_desc(ActionJournalColumn::Id)
+            .into_tuple::<String>()
+            .one(&self.db)
+            .await
+    }
+
+    // Entries of the batch which were not restored yet, including the ones an undo failed on
+    pub async fn find_unrestored_by_batch_id(
+        &self,
+        batch_id: &str,
+    ) -> Result<Vec<ActionJournalModel>, sea_orm::DbErr> {
+        deletions()
+            .filter(ActionJournalColumn::BatchId.eq(batch_id))
+            .filter(
+                ActionJournalColumn::Id
+                    .not_in_subquery(reverting_entry_ids(&[FileOperation::Restore])),
+            )
+            .order_by_asc(ActionJournalColumn::Id)
+            .all(&self.db)
+            .await
+    }
+
+    pub async fn append_many(
+        &self,
+        entries: Vec<models::action_journal::ActionJournalInput>,
+    ) -> Result<(), sea_orm::DbErr> {
+        insert_entries(&self.db, entries).await
+    }
+
+    // Append the entries in a transaction which is only committed when the operation succeeds,
+    // for operations which can not be rolled back once they are done
+    pub async fn append_many_with<T, E: From<sea_orm::DbErr>>(
+        &self,
+        entries: Vec<models::action_journal::ActionJournalInput>,
+        operation: impl FnOnce() -> Result<T, E>,
+    ) -> Result<T, E> {
+        let txn = self.db.begin().await?;
+        insert_entries(&txn, entries).await?;
+        let result = operation()?;
+        txn.commit().await?;
+
+        Ok(result)
+    }
+}
+
+// Entries which an undo can target
+// Links are left out, as they replace the content of the file on purpose
+fn deletions() -> Select<ActionJournalEntity> {
+    ActionJournalEntity::find().filter(ActionJournalColumn::Operation.is_in([
+        FileOperation::Trash.as_str(),
+        FileOperation::Quarantine.as_str(),
+        FileOperation::Delete.as_str(),
+    ]))
+}
+
+// Ids of the entries which were reversed by entries with one of the operations
+fn reverting_entry_ids(operations: &[FileOperation]) -> sea_orm::sea_query::SelectStatement {
+    Query::select()
+        .column(ActionJournalColumn::RevertsEntryId)
+        .from(ActionJournalEntity)
+        .and_where(ActionJournalColumn::RevertsEntryId.is_not_null())
+        .and_where(ActionJournalColumn::Operation.is_in(operations.iter().map(|o| o.as_str())))
+        .to_owned()
+}
+
+async fn insert_entries(
+    db: &impl ConnectionTrait,
+    entries: Vec<models::action_journal::ActionJournalInput>,
+) -> Result<(), sea_orm::DbErr> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    let models = entries
+        .into_iter()
+        .map(|e| ActionJournalActiveModel {
+            batch_id: Set(e.batch_id),
+            operation: Set(e.operation.as_str().to_string()),
+            source: Set(e.source),
+            destination: Set(e.destination),
+            digest_before: Set(e.digest_before),
+            digest_after: Set(e.digest_after),
+            reverts_batch_id: Set(e.reverts_batch_id),
+            reverts_entry_id: Set(e.reverts_entry_id),
+            ..Default::default()
+        })
+        .collect::<Vec<ActionJournalActiveModel>>();
+
+    ActionJournalEntity::insert_many(models)
+        .exec_without_returning(db)
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use migration::MigratorTrait;
+
+    use super::*;
+    use crate::models::action_journal::ActionJournalInput;
+
+    async fn setup() -> sea_orm::DatabaseConnection {
+        let db = sea_orm::Database::connect("sqlite::memory:").await.unwrap();
+
+        migration::Migrator::up(&db, None).await.unwrap();
+
+        db
+    }
+
+    async fn get_action_journal_repository() -> ActionJournalRepository {
+        let db = setup().await;
+        ActionJournalRepository::new(db)
+    }
+
+    fn input(batch_id: &str, source: &str, reverts_batch_id: Option<&str>) -> ActionJournalInput {
+        ActionJournalInput {
+            batch_id: batch_id.into(),
+            operation: if reverts_batch_id.is_some() {
+                FileOperation::Restore
+            } else {
+                FileOperation::Trash
+            },
+            source: source.into(),
+            destination: Some(format!("/trash/{source}")),
+            digest_before: Some("digest".into()),
+            digest_after: Some("digest".into()),
+            reverts_batch_id: reverts_batch_id.map(|b| b.into()),
+            reverts_entry_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_append_many() {
+        let repo = get_action_journal_repository().await;
+
+        repo.append_many(vec![
+            input("batch_1", "test_1.png", None),
+            input("batch_1", "test_2.png", None),
+            input("batch_2", "test_3.png", None),
+        ])
+        .await
+        .unwrap();
+
+        let result = repo.find_unrestored_by_batch_id("batch_1").await.unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].source, "test_1.png");
+        assert_eq!(result[0].operation, "trash");
+        assert_eq!(result[1].source, "test_2.png");
+
+        let result = repo.find_recent(2).await.unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].source, "test_3.png");
+    }
+
+    fn restore(batch_id: &str, reverted: &str, reverts_entry_id: i32) -> ActionJournalInput {
+        ActionJournalInput {
+            reverts_entry_id: Some(reverts_entry_id),
+            ..input(batch_id, &format!("/trash/{reverted}"), Some("batch_1"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_find_last_undoable_batch_id() {
+        let repo = get_action_journal_repository().await;
+
+        let result = repo.find_last_undoable_batch_id().await.unwrap();
+        assert_eq!(result, None);
+
+        repo.append_many(vec![
+            input("batch_1", "test_1.png", None),
+            input("batch_2", "test_2.png", None),
+        ])
+        .await
+        .unwrap();
+
+        let result = repo.find_last_undoable_batch_id().await.unwrap();
+        assert_eq!(result, Some("batch_2".into()));
+
+        repo.append_many(vec![restore("batch_3", "test_2.png", 2)])
+            .await
+            .unwrap();
+
+        let result = repo.find_last_undoable_batch_id().await.unwrap();
+        assert_eq!(result, Some("batch_1".into()));
+    }
+
+    #[tokio::test]
+    async fn test_find_last_undoable_batch_id_targets_irreversible_batches() {
+        let repo = get_action_journal_repository().await;
+
+        repo.append_many(vec![
+            input("batch_1", "test_1.png", None),
+            ActionJournalInput {
+                operation: FileOperation::Delete,
+                destination: None,
+                digest_after: None,
+                ..input("batch_2", "test_2.png", None)
+            },
+            ActionJournalInput {
+                operation: FileOperation::Link,
+                destination: Some("test_1.png".into()),
+                ..input("batch_3", "test_3.png", None)
+            },
+        ])
+        .await
+        .unwrap();
+
+        // The permanent delete is the most recent batch, links are never undone
+        let result = repo.find_last_undoable_batch_id().await.unwrap();
+        assert_eq!(result, Some("batch_2".into()));
+
+        // Once the undo reported its files, the batch before it is next
+        repo.append_many(vec![ActionJournalInput {
+            operation: FileOperation::RestoreFailed,
+            ..restore("batch_4", "test_2.png", 2)
+        }])
+        .await
+        .unwrap();
+
+        let result = repo.find_last_undoable_batch_id().await.unwrap();
+        assert_eq!(result, Some("batch_1".into()));
+    }
+
+    #[tokio::test]
+    async fn test_find_unrestored_by_batch_id() {
+        let repo = get_action_journal_repository().await;
+
+        repo.append_many(vec![
+            input("batch_1", "test_1.png", None),
+            input("batch_1", "test_2.png", None),
+            restore("batch_2", "test_1.png", 1),
+            ActionJournalInput {
+                operation: FileOperation::RestoreFailed,
+                ..restore("batch_2", "test_2.png", 2)
+            },
+        ])
+        .await
+        .unwrap();
+
+        // Every entry was tried, but the failed one can still be retried
+        assert_eq!(repo.find_last_undoable_batch_id().await.unwrap(), None);
+        let result = repo.find_unrestored_by_batch_id("batch_1").await.unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].source, "test_2.png");
+        assert!(repo
+            .find_unrestored_by_batch_id("batch_2")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_append_many_with() {
+        let repo = get_action_journal_repository().await;
+
+        let result = repo
+            .append_many_with(vec![input("batch_1", "test_1.png", None)], || {
+                Err::<(), _>(anyhow::anyhow!("Failed"))
+            })
+            .await;
+        assert!(result.is_err());
+        assert!(repo
+            .find_unrestored_by_batch_id("batch_1")
+            .await
+            .unwrap()
+            .is_empty());
+
+        repo.append_many_with(vec![input("batch_2", "test_2.png", None)], || {
+            Ok::<_, anyhow::Error>(())
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            repo.find_unrestored_by_batch_id("batch_2")
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_journal_is_append_only() {
+        let db = setup().await;
+        let repo = ActionJournalRepository::new(db.clone());
+
+        repo.append_many(vec![input("batch_1", "test_1.png", None)])
+            .await
+            .unwrap();
+
+        let result = ActionJournalEntity::delete_many().exec(&db).await;
+        assert!(result.is_err());
+    }
+}
@@ -1 +1,3 @@
+pub mod action_journal_repository;
+pub mod quarantine_entry_repository;
 pub mod reference_image_repository;
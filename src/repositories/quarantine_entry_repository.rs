@@ -0,0 +1,214 @@
+use chrono::{DateTime, Utc};
+use entity::quarantine_entry::ActiveModel as QuarantineEntryActiveModel;
+use entity::quarantine_entry::Column as QuarantineEntryColumn;
+use entity::quarantine_entry::Entity as QuarantineEntryEntity;
+use entity::quarantine_entry::Model as QuarantineEntryModel;
+use sea_orm::ActiveValue::Set;
+use sea_orm::ColumnTrait;
+use sea_orm::QueryFilter;
+use sea_orm::QueryOrder;
+use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait};
+
+use crate::models;
+
+pub struct QuarantineEntryRepository {
+    db: DatabaseConnection,
+}
+
+impl QuarantineEntryRepository {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    pub async fn find_all(&self) -> Result<Vec<QuarantineEntryModel>, sea_orm::DbErr> {
+        QuarantineEntryEntity::find()
+            .order_by_asc(QuarantineEntryColumn::Id)
+            .all(&self.db)
+            .await
+    }
+
+    pub async fn find_by_ids(
+        &self,
+        ids: &[i32],
+    ) -> Result<Vec<QuarantineEntryModel>, sea_orm::DbErr> {
+        QuarantineEntryEntity::find()
+            .filter(QuarantineEntryColumn::Id.is_in(ids.to_vec()))
+            .order_by_asc(QuarantineEntryColumn::Id)
+            .all(&self.db)
+            .await
+    }
+
+    pub async fn find_by_quarantine_path(
+        &self,
+        quarantine_path: &str,
+    ) -> Result<Option<QuarantineEntryModel>, sea_orm::DbErr> {
+        QuarantineEntryEntity::find()
+            .filter(QuarantineEntryColumn::QuarantinePath.eq(quarantine_path))
+            .one(&self.db)
+            .await
+    }
+
+    // created_at is stored by SQLite as text like "YYYY-MM-DD HH:MM:SS",
+    // so the cutoff is compared in the same format
+    pub async fn find_created_before(
+        &self,
+        created_before: DateTime<Utc>,
+    ) -> Result<Vec<QuarantineEntryModel>, sea_orm::DbErr> {
+        QuarantineEntryEntity::find()
+            .filter(
+                QuarantineEntryColumn::CreatedAt
+                    .lt(created_before.format("%Y-%m-%d %H:%M:%S").to_string()),
+            )
+            .order_by_asc(QuarantineEntryColumn::Id)
+            .all(&self.db)
+            .await
+    }
+
+    pub async fn create(
+        &self,
+        quarantine_entry: models::quarantine_entry::QuarantineEntryInput,
+    ) -> Result<QuarantineEntryModel, sea_orm::DbErr> {
+        QuarantineEntryActiveModel {
+            original_path: Set(quarantine_entry.original_path),
+            quarantine_path: Set(quarantine_entry.quarantine_path),
+            digest: Set(quarantine_entry.digest),
+            ..Default::default()
+        }
+        .insert(&self.db)
+        .await
+    }
+
+    pub async fn delete(&self, id: i32) -> Result<u64, sea_orm::DbErr> {
+        let result = QuarantineEntryEntity::delete_by_id(id)
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use migration::MigratorTrait;
+
+    use super::*;
+
+    async fn setup() -> sea_orm::DatabaseConnection {
+        let db = sea_orm::Database::connect("sqlite::memory:").await.unwrap();
+
+        migration::Migrator::up(&db, None).await.unwrap();
+
+        db
+    }
+
+    async fn get_quarantine_entry_repository() -> QuarantineEntryRepository {
+        let db = setup().await;
+        QuarantineEntryRepository::new(db)
+    }
+
+    fn input(name: &str) -> models::quarantine_entry::QuarantineEntryInput {
+        models::quarantine_entry::QuarantineEntryInput {
+            original_path: format!("/images/{name}"),
+            quarantine_path: format!("/quarantine/{name}"),
+            digest: format!("digest_{name}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create() {
+        let repo = get_quarantine_entry_repository().await;
+
+        let result = repo.create(input("test_1.png")).await.unwrap();
+        assert_eq!(result.original_path, "/images/test_1.png");
+        assert_eq!(result.quarantine_path, "/quarantine/test_1.png");
+        assert_eq!(result.digest, "digest_test_1.png");
+
+        let result = repo.find_all().await.unwrap();
+        assert_eq!(result.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_find_by_ids() {
+        let repo = get_quarantine_entry_repository().await;
+
+        repo.create(input("test_1.png")).await.unwrap();
+        let entry = repo.create(input("test_2.png")).await.unwrap();
+
+        let result = repo.find_by_ids(&[entry.id]).await.unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].original_path, "/images/test_2.png");
+    }
+
+    #[tokio::test]
+    async fn test_find_by_quarantine_path() {
+        let repo = get_quarantine_entry_repository().await;
+
+        repo.create(input("test_1.png")).await.unwrap();
+
+        let result = repo
+            .find_by_quarantine_path("/quarantine/test_1.png")
+            .await
+            .unwrap();
+        assert_eq!(result.unwrap().original_path, "/images/test_1.png");
+
+        let result = repo
+            .find_by_quarantine_path("/quarantine/test_2.png")
+            .await
+            .unwrap();
+        assert!(result.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_find_created_before() {
+        let repo = get_quarantine_entry_repository().await;
+
+        repo.create(input("test_1.png")).await.unwrap();
+
+        let result = repo
+            .find_created_before(Utc::now() - chrono::Duration::days(1))
+            .await
+            .unwrap();
+        assert_eq!(result.len(), 0);
+
+        let result = repo
+            .find_created_before(Utc::now() + chrono::Duration::days(1))
+            .await
+            .unwrap();
+        assert_eq!(result.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_find_created_before_same_day() {
+        let repo = get_quarantine_entry_repository().await;
+
+        let entry = repo.create(input("test_1.png")).await.unwrap();
+
+        // The entry was created after the cutoff on the same day
+        let start_of_day = entry
+            .created_at
+            .date_naive()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc();
+        let result = repo.find_created_before(start_of_day).await.unwrap();
+        assert_eq!(result.len(), 0);
+
+        let result = repo
+            .find_created_before(entry.created_at + chrono::Duration::seconds(1))
+            .await
+            .unwrap();
+        assert_eq!(result.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_delete() {
+        let repo = get_quarantine_entry_repository().await;
+
+        let entry = repo.create(input("test_1.png")).await.unwrap();
+
+        let result = repo.delete(entry.id).await.unwrap();
+        assert_eq!(result, 1);
+
+        let result = repo.find_all().await.unwrap();
+        assert_eq!(result.len(), 0);
+    }
+}
@@ -1,15 +1,25 @@
+use std::collections::{HashMap, HashSet};
+
 use entity::reference_image::ActiveModel as ReferenceImageActiveModel;
 use entity::reference_image::Column as ReferenceImageColumn;
 use entity::reference_image::Entity as ReferenceImageEntity;
 use entity::reference_image::Model as ReferenceImageModel;
+use sea_orm::sea_query::{Expr, OnConflict};
 use sea_orm::ActiveValue::Set;
 use sea_orm::ColumnTrait;
+use sea_orm::PaginatorTrait;
 use sea_orm::QueryFilter;
 use sea_orm::QueryOrder;
-use sea_orm::{DatabaseConnection, EntityTrait};
+use sea_orm::QuerySelect;
+use sea_orm::Select;
+use sea_orm::TransactionTrait;
+use sea_orm::{DatabaseConnection, EntityTrait, Order};
 
 use crate::models;
 
+// Keep the number of bound parameters per statement well below the SQLite limit
+const UPSERT_CHUNK_SIZE: usize = 500;
+
 pub struct ReferenceImageRepository {
     db: DatabaseConnection,
 }
@@ -37,17 +47,71 @@ impl ReferenceImageRepository {
             .await
     }
 
+    pub async fn find_by_ids(
+        &self,
+        ids: &[i32],
+    ) -> Result<Vec<ReferenceImageModel>, sea_orm::DbErr> {
+        ReferenceImageEntity::find()
+            .filter(ReferenceImageColumn::Id.is_in(ids.to_vec()))
+            .order_by_asc(ReferenceImageColumn::Id)
+            .all(&self.db)
+            .await
+    }
+
+    pub async fn find_by_collections(
+        &self,
+        collections: &[String],
+    ) -> Result<Vec<ReferenceImageModel>, sea_orm::DbErr> {
+        ReferenceImageEntity::find()
+            .filter(ReferenceImageColumn::Collection.is_in(collections.to_vec()))
+            .order_by_asc(ReferenceImageColumn::Id)
+            .all(&self.db)
+            .await
+    }
+
+    pub async fn find_collections(&self) -> Result<Vec<String>, sea_orm::DbErr> {
+        ReferenceImageEntity::find()
+            .select_only()
+            .column(ReferenceImageColumn::Collection)
+            .distinct()
+            .filter(ReferenceImageColumn::Collection.is_not_null())
+            .order_by_asc(ReferenceImageColumn::Collection)
+            .into_tuple::<String>()
+            .all(&self.db)
+            .await
+    }
+
+    // Page of the reference images matching the query, with the number of matching rows
+    // The file status is not stored, so it is not filtered here
+    pub async fn find_page(
+        &self,
+        query: &models::reference_image::ReferenceImageQuery,
+    ) -> Result<(Vec<ReferenceImageModel>, u64), sea_orm::DbErr> {
+        let select = find_matching(query);
+        let total = select.clone().count(&self.db).await?;
+        let reference_images = select
+            .offset(query.offset())
+            .limit(query.limit())
+            .all(&self.db)
+            .await?;
+        Ok((reference_images, total))
+    }
+
+    // Every reference image matching the query, in the order of the query
+    pub async fn find_all_matching(
+        &self,
+        query: &models::reference_image::ReferenceImageQuery,
+    ) -> Result<Vec<ReferenceImageModel>, sea_orm::DbErr> {
+        find_matching(query).all(&self.db).await
+    }
+
     pub async fn create_many(
         &self,
         reference_images: Vec<models::reference_image::ReferenceImageInput>,
     ) -> Result<i32, sea_orm::DbErr> {
         let models = reference_images
             .iter()
-            .map(|r| ReferenceImageActiveModel {
-                filepath: Set(r.filepath.clone()),
-                hash: Set(r.hash.clone()),
-                ..Default::default()
-            })
+            .map(to_active_model)
             .collect::<Vec<ReferenceImageActiveModel>>();
 
         let result = ReferenceImageEntity::insert_many(models)
@@ -57,6 +121,95 @@ impl ReferenceImageRepository {
         Ok(result.last_insert_id)
     }
 
+    // Insert the given reference images, or update the rows which already have the same filepath
+    // All the rows are written in a single transaction
+    pub async fn upsert_many(
+        &self,
+        reference_images: Vec<models::reference_image::ReferenceImageInput>,
+    ) -> Result<models::reference_image::UpsertSummary, sea_orm::DbErr> {
+        let mut summary = models::reference_image::UpsertSummary::default();
+        if reference_images.is_empty() {
+            return Ok(summary);
+        }
+
+        // A file given twice is saved once, as if the inputs were upserted one after another:
+        // the last input wins and keeps the collection of an earlier one unless it has its own
+        let mut indices = HashMap::<String, usize>::new();
+        let mut deduplicated: Vec<models::reference_image::ReferenceImageInput> = Vec::new();
+        for reference_image in reference_images {
+            match indices.get(&reference_image.filepath) {
+                Some(&i) => {
+                    let collection = deduplicated[i].collection.take();
+                    deduplicated[i] = reference_image;
+                    deduplicated[i].collection = deduplicated[i].collection.take().or(collection);
+                }
+                None => {
+                    indices.insert(reference_image.filepath.clone(), deduplicated.len());
+                    deduplicated.push(reference_image);
+                }
+            }
+        }
+        let reference_images = deduplicated;
+
+        let txn = self.db.begin().await?;
+
+        let filepaths = reference_images
+            .iter()
+            .map(|r| r.filepath.clone())
+            .collect::<Vec<_>>();
+        let mut existing_filepaths = HashSet::new();
+        for chunk in filepaths.chunks(UPSERT_CHUNK_SIZE) {
+            existing_filepaths.extend(
+                ReferenceImageEntity::find()
+                    .select_only()
+                    .column(ReferenceImageColumn::Filepath)
+                    .filter(ReferenceImageColumn::Filepath.is_in(chunk.to_vec()))
+                    .into_tuple::<String>()
+                    .all(&txn)
+                    .await?,
+            );
+        }
+
+        for chunk in reference_images.chunks(UPSERT_CHUNK_SIZE) {
+            let models = chunk
+                .iter()
+                .map(to_active_model)
+                .collect::<Vec<ReferenceImageActiveModel>>();
+
+            ReferenceImageEntity::insert_many(models)
+                .on_conflict(
+                    OnConflict::column(ReferenceImageColumn::Filepath)
+                        .update_columns([
+                            ReferenceImageColumn::Hash,
+                            ReferenceImageColumn::HashVersion,
+                            ReferenceImageColumn::Digest,
+                            ReferenceImageColumn::Width,
+                            ReferenceImageColumn::Height,
+                        ])
+                        // Keep the current collection unless a new one is given
+                        .value(
+                            ReferenceImageColumn::Collection,
+                            Expr::cust("COALESCE(excluded.collection, reference_image.collection)"),
+                        )
+                        .to_owned(),
+                )
+                .exec_without_returning(&txn)
+                .await?;
+        }
+
+        txn.commit().await?;
+
+        for filepath in filepaths {
+            if existing_filepaths.contains(&filepath) {
+                summary.updated.push(filepath);
+            } else {
+                summary.inserted.push(filepath);
+            }
+        }
+
+        Ok(summary)
+    }
+
     pub async fn update(
         &self,
         reference_image: ReferenceImageActiveModel,
@@ -68,12 +221,101 @@ impl ReferenceImageRepository {
         Ok(())
     }
 
+    pub async fn update_hash(
+        &self,
+        id: i32,
+        hash: Vec<u8>,
+        hash_version: i32,
+    ) -> Result<(), sea_orm::DbErr> {
+        let reference_image = ReferenceImageActiveModel {
+            id: Set(id),
+            hash: Set(hash),
+            hash_version: Set(hash_version),
+            ..Default::default()
+        };
+        self.update(reference_image).await
+    }
+
+    pub async fn update_collection(
+        &self,
+        ids: &[i32],
+        collection: Option<String>,
+    ) -> Result<u64, sea_orm::DbErr> {
+        let result = ReferenceImageEntity::update_many()
+            .col_expr(ReferenceImageColumn::Collection, Expr::value(collection))
+            .filter(ReferenceImageColumn::Id.is_in(ids.to_vec()))
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
     pub async fn delete(&self, id: i32) -> Result<u64, sea_orm::DbErr> {
         let result = ReferenceImageEntity::delete_by_id(id)
             .exec(&self.db)
             .await?;
         Ok(result.rows_affected)
     }
+
+    pub async fn delete_many(&self, ids: &[i32]) -> Result<u64, sea_orm::DbErr> {
+        let result = ReferenceImageEntity::delete_many()
+            .filter(ReferenceImageColumn::Id.is_in(ids.to_vec()))
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
+
+fn find_matching(
+    query: &models::reference_image::ReferenceImageQuery,
+) -> Select<ReferenceImageEntity> {
+    use models::reference_image::ReferenceImageSortKey;
+
+    let mut select = ReferenceImageEntity::find();
+    if !query.path.is_empty() {
+        // LIKE is case-insensitive for ASCII in SQLite
+        select = select.filter(ReferenceImageColumn::Filepath.contains(&query.path));
+    }
+    if let Some(collection) = &query.collection {
+        select = select.filter(ReferenceImageColumn::Collection.eq(collection));
+    }
+    // Timestamps are stored as text starting with the date, so they are compared with the date
+    if let Some(created_from) = query.created_from {
+        select = select.filter(ReferenceImageColumn::CreatedAt.gte(created_from.to_string()));
+    }
+    if let Some(created_to) = query.created_to.and_then(|d| d.succ_opt()) {
+        select = select.filter(ReferenceImageColumn::CreatedAt.lt(created_to.to_string()));
+    }
+
+    let column = match query.sort_key {
+        ReferenceImageSortKey::CreatedAt => ReferenceImageColumn::CreatedAt,
+        ReferenceImageSortKey::UpdatedAt => ReferenceImageColumn::UpdatedAt,
+        ReferenceImageSortKey::Path => ReferenceImageColumn::Filepath,
+        ReferenceImageSortKey::Collection => ReferenceImageColumn::Collection,
+    };
+    let order = if query.is_descending {
+        Order::Desc
+    } else {
+        Order::Asc
+    };
+    // Ties keep the order of the ids, so pages don't overlap
+    select
+        .order_by(column, order.clone())
+        .order_by(ReferenceImageColumn::Id, order)
+}
+
+fn to_active_model(
+    reference_image: &models::reference_image::ReferenceImageInput,
+) -> ReferenceImageActiveModel {
+    ReferenceImageActiveModel {
+        filepath: Set(reference_image.filepath.clone()),
+        hash: Set(reference_image.hash.clone()),
+        hash_version: Set(reference_image.hash_version),
+        digest: Set(reference_image.digest.clone()),
+        width: Set(reference_image.width),
+        height: Set(reference_image.height),
+        collection: Set(reference_image.collection.clone()),
+        ..Default::default()
+    }
 }
 
 #[cfg(test)]
@@ -106,10 +348,14 @@ mod tests {
             models::reference_image::ReferenceImageInput {
                 filepath: "test_1.png".into(),
                 hash: "test_hash_1".into(),
+                hash_version: 1,
+                ..Default::default()
             },
             models::reference_image::ReferenceImageInput {
                 filepath: "test_2.png".into(),
                 hash: "test_hash_2".into(),
+                hash_version: 1,
+                ..Default::default()
             },
         ])
         .await
@@ -140,10 +386,14 @@ mod tests {
             models::reference_image::ReferenceImageInput {
                 filepath: "test_1.png".into(),
                 hash: "test_hash_1".into(),
+                hash_version: 1,
+                ..Default::default()
             },
             models::reference_image::ReferenceImageInput {
                 filepath: "test_3.png".into(),
                 hash: "test_hash_3".into(),
+                hash_version: 1,
+                ..Default::default()
             },
         ])
         .await
@@ -157,6 +407,146 @@ mod tests {
         assert_eq!(result[1].hash, "test_hash_3".as_bytes());
     }
 
+    #[tokio::test]
+    async fn test_find_by_ids() {
+        let repo = get_reference_image_repository().await;
+
+        repo.create_many(vec![
+            models::reference_image::ReferenceImageInput {
+                filepath: "test_1.png".into(),
+                hash: "test_hash_1".into(),
+                hash_version: 1,
+                ..Default::default()
+            },
+            models::reference_image::ReferenceImageInput {
+                filepath: "test_2.png".into(),
+                hash: "test_hash_2".into(),
+                hash_version: 1,
+                ..Default::default()
+            },
+        ])
+        .await
+        .unwrap();
+
+        let all = repo.find_all().await.unwrap();
+        let result = repo.find_by_ids(&[all[1].id]).await.unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].filepath, "test_2.png");
+    }
+
+    #[tokio::test]
+    async fn test_find_by_collections() {
+        let repo = get_reference_image_repository().await;
+
+        repo.create_many(vec![
+            models::reference_image::ReferenceImageInput {
+                filepath: "test_1.png".into(),
+                hash: "test_hash_1".into(),
+                hash_version: 1,
+                collection: Some("collection_1".into()),
+                ..Default::default()
+            },
+            models::reference_image::ReferenceImageInput {
+                filepath: "test_2.png".into(),
+                hash: "test_hash_2".into(),
+                hash_version: 1,
+                collection: Some("collection_2".into()),
+                ..Default::default()
+            },
+            models::reference_image::ReferenceImageInput {
+                filepath: "test_3.png".into(),
+                hash: "test_hash_3".into(),
+                hash_version: 1,
+                ..Default::default()
+            },
+        ])
+        .await
+        .unwrap();
+
+        let result = repo
+            .find_by_collections(&["collection_2".into()])
+            .await
+            .unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].filepath, "test_2.png");
+
+        let result = repo.find_collections().await.unwrap();
+        assert_eq!(result, vec!["collection_1", "collection_2"]);
+    }
+
+    #[tokio::test]
+    async fn test_find_page() {
+        use models::reference_image::{ReferenceImageQuery, ReferenceImageSortKey};
+
+        let repo = get_reference_image_repository().await;
+        repo.create_many(
+            (1..=5)
+                .map(|i| models::reference_image::ReferenceImageInput {
+                    filepath: format!("/images/Test_{i}.png"),
+                    hash: format!("test_hash_{i}").into(),
+                    hash_version: 1,
+                    collection: Some(format!("collection_{}", i % 2)),
+                    ..Default::default()
+                })
+                .collect(),
+        )
+        .await
+        .unwrap();
+
+        let query = ReferenceImageQuery {
+            sort_key: ReferenceImageSortKey::Path,
+            is_descending: false,
+            page: 1,
+            page_size: 2,
+            ..Default::default()
+        };
+        let (result, total) = repo.find_page(&query).await.unwrap();
+        assert_eq!(total, 5);
+        assert_eq!(
+            result.iter().map(|i| &i.filepath).collect::<Vec<_>>(),
+            ["/images/Test_3.png", "/images/Test_4.png"]
+        );
+
+        let query = ReferenceImageQuery {
+            path: "test_".into(),
+            collection: Some("collection_1".into()),
+            ..Default::default()
+        };
+        let (result, total) = repo.find_page(&query).await.unwrap();
+        assert_eq!(total, 3);
+        assert_eq!(
+            result.iter().map(|i| &i.filepath).collect::<Vec<_>>(),
+            [
+                "/images/Test_5.png",
+                "/images/Test_3.png",
+                "/images/Test_1.png"
+            ]
+        );
+
+        let today = chrono::Utc::now().date_naive();
+        let query = ReferenceImageQuery {
+            created_from: Some(today),
+            created_to: Some(today),
+            ..Default::default()
+        };
+        assert_eq!(repo.find_all_matching(&query).await.unwrap().len(), 5);
+        let query = ReferenceImageQuery {
+            created_from: today.succ_opt(),
+            ..Default::default()
+        };
+        assert!(repo.find_all_matching(&query).await.unwrap().is_empty());
+
+        // Oversized pages are clamped instead of overflowing
+        let query = ReferenceImageQuery {
+            page: u64::MAX,
+            page_size: u64::MAX,
+            ..Default::default()
+        };
+        let (result, total) = repo.find_page(&query).await.unwrap();
+        assert_eq!(total, 5);
+        assert!(result.is_empty());
+    }
+
     #[tokio::test]
     async fn test_create_many() {
         let repo = get_reference_image_repository().await;
@@ -169,10 +559,14 @@ mod tests {
                 models::reference_image::ReferenceImageInput {
                     filepath: "test_1.png".into(),
                     hash: "test_hash_1".into(),
+                    hash_version: 1,
+                    ..Default::default()
                 },
                 models::reference_image::ReferenceImageInput {
                     filepath: "test_2.png".into(),
                     hash: "test_hash_2".into(),
+                    hash_version: 1,
+                    ..Default::default()
                 },
             ])
             .await
@@ -187,6 +581,122 @@ mod tests {
         assert_eq!(result[1].id, last_id);
     }
 
+    #[tokio::test]
+    async fn test_upsert_many() {
+        let repo = get_reference_image_repository().await;
+
+        let summary = repo
+            .upsert_many(vec![
+                models::reference_image::ReferenceImageInput {
+                    filepath: "test_1.png".into(),
+                    hash: "test_hash_1".into(),
+                    hash_version: 1,
+                    collection: Some("collection_1".into()),
+                    ..Default::default()
+                },
+                models::reference_image::ReferenceImageInput {
+                    filepath: "test_2.png".into(),
+                    hash: "test_hash_2".into(),
+                    hash_version: 1,
+                    ..Default::default()
+                },
+            ])
+            .await
+            .unwrap();
+        assert_eq!(summary.inserted, vec!["test_1.png", "test_2.png"]);
+        assert!(summary.updated.is_empty());
+
+        let summary = repo
+            .upsert_many(vec![
+                models::reference_image::ReferenceImageInput {
+                    filepath: "test_1.png".into(),
+                    hash: "hash_changed".into(),
+                    hash_version: 2,
+                    ..Default::default()
+                },
+                models::reference_image::ReferenceImageInput {
+                    filepath: "test_3.png".into(),
+                    hash: "test_hash_3".into(),
+                    hash_version: 1,
+                    ..Default::default()
+                },
+            ])
+            .await
+            .unwrap();
+        assert_eq!(summary.inserted, vec!["test_3.png"]);
+        assert_eq!(summary.updated, vec!["test_1.png"]);
+
+        let result = repo.find_all().await.unwrap();
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[0].filepath, "test_1.png");
+        assert_eq!(result[0].hash, "hash_changed".as_bytes());
+        assert_eq!(result[0].hash_version, 2);
+        assert_eq!(result[0].collection, Some("collection_1".into()));
+        assert_eq!(result[2].filepath, "test_3.png");
+    }
+
+    #[tokio::test]
+    async fn test_upsert_many_deduplicates_filepaths() {
+        let repo = get_reference_image_repository().await;
+
+        let summary = repo
+            .upsert_many(vec![
+                models::reference_image::ReferenceImageInput {
+                    filepath: "test_1.png".into(),
+                    hash: "test_hash_1".into(),
+                    hash_version: 1,
+                    collection: Some("collection_1".into()),
+                    ..Default::default()
+                },
+                models::reference_image::ReferenceImageInput {
+                    filepath: "test_2.png".into(),
+                    hash: "test_hash_2".into(),
+                    hash_version: 1,
+                    ..Default::default()
+                },
+                models::reference_image::ReferenceImageInput {
+                    filepath: "test_1.png".into(),
+                    hash: "hash_changed".into(),
+                    hash_version: 2,
+                    ..Default::default()
+                },
+            ])
+            .await
+            .unwrap();
+        assert_eq!(summary.inserted, vec!["test_1.png", "test_2.png"]);
+        assert!(summary.updated.is_empty());
+
+        let result = repo.find_all().await.unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].hash, "hash_changed".as_bytes());
+        assert_eq!(result[0].hash_version, 2);
+        assert_eq!(result[0].collection, Some("collection_1".into()));
+    }
+
+    #[tokio::test]
+    async fn test_filepath_is_unique() {
+        let repo = get_reference_image_repository().await;
+
+        repo.create_many(vec![models::reference_image::ReferenceImageInput {
+            filepath: "test_1.png".into(),
+            hash: "test_hash_1".into(),
+            hash_version: 1,
+            ..Default::default()
+        }])
+        .await
+        .unwrap();
+
+        let result = repo
+            .create_many(vec![models::reference_image::ReferenceImageInput {
+                filepath: "test_1.png".into(),
+                hash: "test_hash_2".into(),
+                hash_version: 1,
+                ..Default::default()
+            }])
+            .await;
+        assert!(result.is_err());
+    }
+
     #[tokio::test]
     async fn test_update() {
         let repo = get_reference_image_repository().await;
@@ -194,6 +704,8 @@ mod tests {
         repo.create_many(vec![models::reference_image::ReferenceImageInput {
             filepath: "test_1.png".into(),
             hash: "test_hash_1".into(),
+            hash_version: 1,
+            ..Default::default()
         }])
         .await
         .unwrap();
@@ -213,6 +725,69 @@ mod tests {
         assert_eq!(result[0].hash, "hash_changed".as_bytes());
     }
 
+    #[tokio::test]
+    async fn test_update_hash() {
+        let repo = get_reference_image_repository().await;
+
+        repo.create_many(vec![models::reference_image::ReferenceImageInput {
+            filepath: "test_1.png".into(),
+            hash: "test_hash_1".into(),
+            hash_version: 1,
+            ..Default::default()
+        }])
+        .await
+        .unwrap();
+
+        let result = repo.find_all().await.unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].hash_version, 1);
+
+        repo.update_hash(result[0].id, "hash_changed".into(), 2)
+            .await
+            .unwrap();
+
+        let result = repo.find_all().await.unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].filepath, "test_1.png");
+        assert_eq!(result[0].hash, "hash_changed".as_bytes());
+        assert_eq!(result[0].hash_version, 2);
+    }
+
+    #[tokio::test]
+    async fn test_update_collection_and_delete_many() {
+        let repo = get_reference_image_repository().await;
+        repo.create_many(
+            (1..=3)
+                .map(|i| models::reference_image::ReferenceImageInput {
+                    filepath: format!("test_{i}.png"),
+                    hash: format!("test_hash_{i}").into(),
+                    hash_version: 1,
+                    ..Default::default()
+                })
+                .collect(),
+        )
+        .await
+        .unwrap();
+        let all = repo.find_all().await.unwrap();
+
+        let updated = repo
+            .update_collection(&[all[0].id, all[2].id], Some("moved".into()))
+            .await
+            .unwrap();
+        assert_eq!(updated, 2);
+        let result = repo.find_by_collections(&["moved".into()]).await.unwrap();
+        assert_eq!(
+            result.iter().map(|i| i.id).collect::<Vec<_>>(),
+            [all[0].id, all[2].id]
+        );
+
+        let deleted = repo.delete_many(&[all[0].id, all[1].id]).await.unwrap();
+        assert_eq!(deleted, 2);
+        let result = repo.find_all().await.unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].filepath, "test_3.png");
+    }
+
     #[tokio::test]
     async fn test_delete() {
         let repo = get_reference_image_repository().await;
@@ -220,6 +795,8 @@ mod tests {
         repo.create_many(vec![models::reference_image::ReferenceImageInput {
             filepath: "test_1.png".into(),
             hash: "test_hash_1".into(),
+            hash_version: 1,
+            ..Default::default()
         }])
         .await
         .unwrap();
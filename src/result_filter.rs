@@ -0,0 +1,316 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashSet},
+    ops::Range,
+    path::Path,
+};
+
+use crate::models::SimilarImage;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Similarity,
+    Path,
+    Size,
+    Dimensions,
+    ModifiedAt,
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 5] = [
+        SortKey::Similarity,
+        SortKey::Path,
+        SortKey::Size,
+        SortKey::Dimensions,
+        SortKey::ModifiedAt,
+    ];
+
+    fn compare(&self, a: &SimilarImage, b: &SimilarImage) -> Ordering {
+        match self {
+            SortKey::Similarity => a.similarity.cmp(&b.similarity),
+            SortKey::Path => a.filepath.cmp(&b.filepath),
+            SortKey::Size => a.size().cmp(&b.size()),
+            SortKey::Dimensions => a.resolution().cmp(&b.resolution()),
+            // Unknown modification times come first
+            SortKey::ModifiedAt => {
+                let modified_at = |i: &SimilarImage| i.fingerprint.as_ref()?.modified_at;
+                modified_at(a).cmp(&modified_at(b))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for SortKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SortKey::Similarity => write!(f, "Similarity"),
+            SortKey::Path => write!(f, "Path"),
+            SortKey::Size => write!(f, "File size"),
+            SortKey::Dimensions => write!(f, "Dimensions"),
+            SortKey::ModifiedAt => write!(f, "Modified"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResultStatus {
+    Pending,
+    Deleted,
+    Error,
+}
+
+impl ResultStatus {
+    pub const ALL: [ResultStatus; 3] = [
+        ResultStatus::Pending,
+        ResultStatus::Deleted,
+        ResultStatus::Error,
+    ];
+
+    pub fn of(similar_image: &SimilarImage) -> Self {
+        if similar_image.error_message.is_some() {
+            ResultStatus::Error
+        } else if similar_image.is_deleted {
+            ResultStatus::Deleted
+        } else {
+            ResultStatus::Pending
+        }
+    }
+}
+
+impl std::fmt::Display for ResultStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResultStatus::Pending => write!(f, "Pending"),
+            ResultStatus::Deleted => write!(f, "Deleted"),
+            ResultStatus::Error => write!(f, "Error"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResultFilter {
+    // Case-insensitive substring of the path
+    pub path: String,
+    pub min_similarity: u32,
+    pub max_similarity: u32,
+    // Directory containing the images, every directory when `None`
+    pub directory: Option<String>,
+    pub statuses: HashSet<ResultStatus>,
+}
+
+impl Default for ResultFilter {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            min_similarity: 0,
+            max_similarity: 100,
+            directory: None,
+            statuses: HashSet::from(ResultStatus::ALL),
+        }
+    }
+}
+
+impl ResultFilter {
+    pub fn matches(&self, similar_image: &SimilarImage) -> bool {
+        let path = self.path.to_lowercase();
+        (path.is_empty() || similar_image.filepath.to_lowercase().contains(&path))
+            && (self.min_similarity..=self.max_similarity).contains(&similar_image.similarity)
+            && self
+                .directory
+                .as_ref()
+                .is_none_or(|d| parent_directory(&similar_image.filepath) == *d)
+            && self.statuses.contains(&ResultStatus::of(similar_image))
+    }
+}
+
+pub fn parent_directory(filepath: &str) -> String {
+    Path::new(filepath)
+        .parent()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+// Directories containing the images, for the directory filter
+pub fn directories(similar_images: &BTreeMap<u32, SimilarImage>) -> Vec<String> {
+    similar_images
+        .values()
+        .map(|i| parent_directory(&i.filepath))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+// Ids of the images matching the filter, in display order
+// Ties keep the order of the ids
+pub fn filter_and_sort(
+    similar_images: &BTreeMap<u32, SimilarImage>,
+    filter: &ResultFilter,
+    sort_key: SortKey,
+    is_descending: bool,
+) -> Vec<u32> {
+    let mut images = similar_images
+        .iter()
+        .filter(|(_, similar_image)| filter.matches(similar_image))
+        .collect::<Vec<_>>();
+    images.sort_by(|(a_id, a), (b_id, b)| {
+        let ordering = sort_key.compare(a, b);
+        let ordering = if is_descending {
+            ordering.reverse()
+        } else {
+            ordering
+        };
+        ordering.then(a_id.cmp(b_id))
+    });
+    images.into_iter().map(|(image_id, _)| *image_id).collect()
+}
+
+// Rows inside the viewport, with `overscan` more rows before and after them
+pub fn visible_range(
+    scroll_top: f64,
+    viewport_height: f64,
+    row_height: f64,
+    row_count: usize,
+    overscan: usize,
+) -> Range<usize> {
+    let scroll_top = scroll_top.max(0.0);
+    let first = (scroll_top / row_height).floor() as usize;
+    let last = ((scroll_top + viewport_height) / row_height).ceil() as usize;
+    first.saturating_sub(overscan).min(row_count)..(last + overscan).min(row_count)
+}
+
+// Image `offset` rows away from the focused one, stopping at the ends of the list
+// Focus starts at the first image when nothing or a hidden image is focused
+pub fn move_focus(image_ids: &[u32], focused_image: Option<u32>, offset: isize) -> Option<u32> {
+    let index = focused_image.and_then(|f| image_ids.iter().position(|image_id| *image_id == f));
+    let index = match index {
+        Some(index) => index
+            .saturating_add_signed(offset)
+            .min(image_ids.len().checked_sub(1)?),
+        None => 0,
+    };
+    image_ids.get(index).copied()
+}
+
+// Scroll offset which brings the row into the viewport, `None` when it is already visible
+pub fn scroll_into_view(
+    scroll_top: f64,
+    viewport_height: f64,
+    row_height: f64,
+    index: usize,
+) -> Option<f64> {
+    let top = index as f64 * row_height;
+    let bottom = top + row_height;
+    if top < scroll_top {
+        Some(top)
+    } else if bottom > scroll_top + viewport_height {
+        Some(bottom - viewport_height)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::FileFingerprint;
+
+    fn similar_image(filepath: &str, similarity: u32, size: u64) -> SimilarImage {
+        SimilarImage {
+            filepath: filepath.to_string(),
+            similarity,
+            matched_reference: None,
+            width: None,
+            height: None,
+            is_deleted: false,
+            error_message: None,
+            fingerprint: Some(FileFingerprint {
+                size,
+                modified_at: None,
+                digest: String::new(),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_filter_and_sort() {
+        let similar_images = BTreeMap::from([
+            (0, similar_image("/images/a.jpg", 95, 300)),
+            (1, similar_image("/images/b.jpg", 99, 100)),
+            (
+                2,
+                SimilarImage {
+                    is_deleted: true,
+                    ..similar_image("/photos/c.jpg", 95, 200)
+                },
+            ),
+            (3, similar_image("/photos/D.jpg", 90, 400)),
+        ]);
+        assert_eq!(directories(&similar_images), ["/images", "/photos"]);
+
+        let filter = ResultFilter::default();
+        assert_eq!(
+            filter_and_sort(&similar_images, &filter, SortKey::Similarity, true),
+            [1, 0, 2, 3]
+        );
+        assert_eq!(
+            filter_and_sort(&similar_images, &filter, SortKey::Size, false),
+            [1, 2, 0, 3]
+        );
+
+        let filter = ResultFilter {
+            path: "d.JPG".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            filter_and_sort(&similar_images, &filter, SortKey::Path, false),
+            [3]
+        );
+
+        let filter = ResultFilter {
+            min_similarity: 91,
+            directory: Some("/photos".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            filter_and_sort(&similar_images, &filter, SortKey::Path, false),
+            [2]
+        );
+
+        let filter = ResultFilter {
+            statuses: HashSet::from([ResultStatus::Pending]),
+            ..Default::default()
+        };
+        assert_eq!(
+            filter_and_sort(&similar_images, &filter, SortKey::Path, false),
+            [0, 1, 3]
+        );
+    }
+
+    #[test]
+    fn test_visible_range() {
+        assert_eq!(visible_range(0.0, 400.0, 80.0, 100, 2), 0..7);
+        assert_eq!(visible_range(800.0, 400.0, 80.0, 100, 2), 8..17);
+        assert_eq!(visible_range(7800.0, 400.0, 80.0, 100, 2), 95..100);
+        assert_eq!(visible_range(0.0, 400.0, 80.0, 3, 2), 0..3);
+        assert_eq!(visible_range(9000.0, 400.0, 80.0, 3, 2), 3..3);
+    }
+
+    #[test]
+    fn test_move_focus() {
+        let image_ids = [4, 2, 7];
+        assert_eq!(move_focus(&image_ids, None, 1), Some(4));
+        assert_eq!(move_focus(&image_ids, Some(9), -1), Some(4));
+        assert_eq!(move_focus(&image_ids, Some(4), 1), Some(2));
+        assert_eq!(move_focus(&image_ids, Some(2), -1), Some(4));
+        assert_eq!(move_focus(&image_ids, Some(4), -1), Some(4));
+        assert_eq!(move_focus(&image_ids, Some(7), 1), Some(7));
+        assert_eq!(move_focus(&[], None, 1), None);
+    }
+
+    #[test]
+    fn test_scroll_into_view() {
+        assert_eq!(scroll_into_view(0.0, 400.0, 80.0, 2), None);
+        assert_eq!(scroll_into_view(0.0, 400.0, 80.0, 5), Some(80.0));
+        assert_eq!(scroll_into_view(400.0, 400.0, 80.0, 3), Some(240.0));
+    }
+}
@@ -0,0 +1,138 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{file_operations, models::FileFingerprint};
+
+// Files matched by the last search
+// Destructive operations are only allowed on these files, and only while they are unchanged
+#[derive(Debug, Clone)]
+pub struct SearchSession {
+    root: PathBuf,
+    matched_files: HashMap<String, FileFingerprint>,
+}
+
+impl SearchSession {
+    pub fn new(root: &Path, matched_files: HashMap<String, FileFingerprint>) -> Result<Self> {
+        let root = root
+            .canonicalize()
+            .with_context(|| format!("Directory not found: {}", root.display()))?;
+        Ok(Self {
+            root,
+            matched_files,
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn contains(&self, filepath: &str) -> bool {
+        self.matched_files.contains_key(filepath)
+    }
+
+    // Check that the file was matched by the search, lies inside the searched directory
+    // and was not modified since the search
+    pub fn verify(&self, filepath: &str) -> Result<()> {
+        let expected = self.expected_fingerprint(filepath)?;
+        self.verify_fingerprint(filepath, expected)
+    }
+
+    // Like `verify`, and the file also has to be the one a dry run checked
+    // A new search may have replaced the session since then
+    pub fn verify_confirmed(&self, filepath: &str, confirmed: &FileFingerprint) -> Result<()> {
+        let expected = self.expected_fingerprint(filepath)?;
+        if expected != confirmed {
+            bail!("The file changed since it was checked");
+        }
+        self.verify_fingerprint(filepath, expected)
+    }
+
+    pub fn expected_fingerprint(&self, filepath: &str) -> Result<&FileFingerprint> {
+        self.matched_files
+            .get(filepath)
+            .context("The file is not part of the search result")
+    }
+
+    fn verify_fingerprint(&self, filepath: &str, expected: &FileFingerprint) -> Result<()> {
+        let path = Path::new(filepath);
+        let parent = path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or(Path::new("."))
+            .canonicalize()
+            .with_context(|| format!("File not found: {filepath}"))?;
+        if !parent.starts_with(&self.root) {
+            bail!("The file is outside of the searched directory");
+        }
+
+        let current = file_operations::fingerprint(path)?;
+        if current.size != expected.size {
+            bail!("The file size changed since the search");
+        }
+        if current.modified_at != expected.modified_at {
+            bail!("The file was modified since the search");
+        }
+        if current.digest != expected.digest {
+            bail!("The file content changed since the search");
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify() {
+        let dir = std::env::temp_dir().join(format!("imgdup-session-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let root = dir.join("images");
+        std::fs::create_dir_all(&root).unwrap();
+
+        let matched = root.join("matched.png");
+        let changed = root.join("changed.png");
+        let unmatched = root.join("unmatched.png");
+        let outside = dir.join("outside.png");
+        for file in [&matched, &changed, &unmatched, &outside] {
+            std::fs::write(file, "test").unwrap();
+        }
+
+        let matched_files = [&matched, &changed, &outside]
+            .into_iter()
+            .map(|f| {
+                (
+                    f.to_string_lossy().to_string(),
+                    file_operations::fingerprint(f).unwrap(),
+                )
+            })
+            .collect();
+        let session = SearchSession::new(&root, matched_files).unwrap();
+
+        std::fs::write(&changed, "changed").unwrap();
+
+        assert!(session.verify(&matched.to_string_lossy()).is_ok());
+        assert!(session.verify(&changed.to_string_lossy()).is_err());
+        assert!(session.verify(&unmatched.to_string_lossy()).is_err());
+        assert!(session.verify(&outside.to_string_lossy()).is_err());
+
+        let fingerprint = file_operations::fingerprint(&matched).unwrap();
+        assert!(session
+            .verify_confirmed(&matched.to_string_lossy(), &fingerprint)
+            .is_ok());
+        let other = FileFingerprint {
+            digest: "other".to_string(),
+            ..fingerprint
+        };
+        assert!(session
+            .verify_confirmed(&matched.to_string_lossy(), &other)
+            .is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,189 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap, HashSet},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::SimilarImage;
+
+// Policies which decide the image to keep in each match group
+// Every other image of the group is selected, so it can be deleted or linked
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum SelectionPolicy {
+    HighestResolution,
+    LargestFile,
+    Oldest,
+    PreferredDirectory,
+    LosslessFormat,
+}
+
+impl SelectionPolicy {
+    pub const ALL: [SelectionPolicy; 5] = [
+        SelectionPolicy::HighestResolution,
+        SelectionPolicy::LargestFile,
+        SelectionPolicy::Oldest,
+        SelectionPolicy::PreferredDirectory,
+        SelectionPolicy::LosslessFormat,
+    ];
+
+    // `Greater` means that `a` is the better one to keep
+    fn compare(&self, a: &SimilarImage, b: &SimilarImage, preferred_directory: &str) -> Ordering {
+        match self {
+            SelectionPolicy::HighestResolution => a.resolution().cmp(&b.resolution()),
+            SelectionPolicy::LargestFile => a.size().cmp(&b.size()),
+            // Unknown modification times are treated as the newest
+            SelectionPolicy::Oldest => {
+                let modified_at = |i: &SimilarImage| i.fingerprint.as_ref()?.modified_at;
+                match (modified_at(a), modified_at(b)) {
+                    (Some(a), Some(b)) => b.cmp(&a),
+                    (a, b) => a.is_some().cmp(&b.is_some()),
+                }
+            }
+            SelectionPolicy::PreferredDirectory => {
+                let is_preferred = |i: &SimilarImage| {
+                    !preferred_directory.is_empty()
+                        && Path::new(&i.filepath).starts_with(preferred_directory)
+                };
+                is_preferred(a).cmp(&is_preferred(b))
+            }
+            SelectionPolicy::LosslessFormat => is_lossless(a).cmp(&is_lossless(b)),
+        }
+    }
+}
+
+impl std::fmt::Display for SelectionPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SelectionPolicy::HighestResolution => write!(f, "Keep the highest resolution"),
+            SelectionPolicy::LargestFile => write!(f, "Keep the largest file"),
+            SelectionPolicy::Oldest => write!(f, "Keep the oldest file"),
+            SelectionPolicy::PreferredDirectory => {
+                write!(f, "Keep the one in the preferred directory")
+            }
+            SelectionPolicy::LosslessFormat => write!(f, "Keep the lossless format"),
+        }
+    }
+}
+
+// Select every image except the one to keep in each group of images matching the same reference
+// Policies are applied in order, each one breaking the ties of the previous ones
+// Remaining ties keep the most similar image
+pub fn select_duplicates(
+    similar_images: &BTreeMap<u32, SimilarImage>,
+    policies: &[SelectionPolicy],
+    preferred_directory: &str,
+) -> HashSet<u32> {
+    let mut groups = HashMap::<&str, Vec<(u32, &SimilarImage)>>::new();
+    for (image_id, similar_image) in similar_images {
+        if similar_image.is_deleted {
+            continue;
+        }
+        if let Some(matched_reference) = &similar_image.matched_reference {
+            groups
+                .entry(matched_reference.as_str())
+                .or_default()
+                .push((*image_id, similar_image));
+        }
+    }
+
+    let mut selected_images = HashSet::new();
+    for images in groups.into_values() {
+        let keeper = images.iter().max_by(|(a_id, a), (b_id, b)| {
+            policies
+                .iter()
+                .map(|p| p.compare(a, b, preferred_directory))
+                .find(|o| o.is_ne())
+                .unwrap_or_else(|| a.similarity.cmp(&b.similarity))
+                .then_with(|| b_id.cmp(a_id))
+        });
+        let Some((keeper_id, _)) = keeper else {
+            continue;
+        };
+        selected_images.extend(
+            images
+                .iter()
+                .map(|(image_id, _)| *image_id)
+                .filter(|image_id| image_id != keeper_id),
+        );
+    }
+
+    selected_images
+}
+
+fn is_lossless(i: &SimilarImage) -> bool {
+    matches!(
+        Path::new(&i.filepath)
+            .extension()
+            .unwrap_or_default()
+            .to_ascii_lowercase()
+            .to_str()
+            .unwrap_or_default(),
+        "png" | "bmp" | "gif" | "tif" | "tiff"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::FileFingerprint;
+
+    fn similar_image(
+        filepath: &str,
+        width: u32,
+        size: u64,
+        matched_reference: &str,
+    ) -> SimilarImage {
+        SimilarImage {
+            filepath: filepath.to_string(),
+            similarity: 95,
+            matched_reference: Some(matched_reference.to_string()),
+            width: Some(width),
+            height: Some(width),
+            is_deleted: false,
+            error_message: None,
+            fingerprint: Some(FileFingerprint {
+                size,
+                modified_at: None,
+                digest: String::new(),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_select_duplicates() {
+        let similar_images = BTreeMap::from([
+            (0, similar_image("/images/a.jpg", 100, 300, "/ref/1.png")),
+            (1, similar_image("/images/b.png", 200, 200, "/ref/1.png")),
+            (2, similar_image("/keep/c.jpg", 200, 100, "/ref/1.png")),
+            (3, similar_image("/images/d.jpg", 100, 100, "/ref/2.png")),
+        ]);
+
+        // The only image of a group is always kept
+        let selected = select_duplicates(&similar_images, &[SelectionPolicy::LargestFile], "");
+        assert_eq!(selected, HashSet::from([1, 2]));
+
+        // Ties on resolution are broken by the next policy
+        let selected = select_duplicates(
+            &similar_images,
+            &[
+                SelectionPolicy::HighestResolution,
+                SelectionPolicy::LosslessFormat,
+            ],
+            "",
+        );
+        assert_eq!(selected, HashSet::from([0, 2]));
+
+        let selected = select_duplicates(
+            &similar_images,
+            &[SelectionPolicy::PreferredDirectory],
+            "/keep",
+        );
+        assert_eq!(selected, HashSet::from([0, 1]));
+
+        // Without policies the first of equally similar images is kept
+        let selected = select_duplicates(&similar_images, &[], "");
+        assert_eq!(selected, HashSet::from([1, 2]));
+    }
+}
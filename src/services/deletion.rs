@@ -0,0 +1,98 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::{di::Container, file_operations, image_processing, models};
+
+// Journal batches are identified by the time they were started
+pub fn new_batch_id() -> String {
+    chrono::Utc::now().format("%Y%m%d%H%M%S%6f").to_string()
+}
+
+// Delete the file and record the operation in the journal so that it can be undone
+pub async fn delete_similar_image(
+    container: &Container,
+    batch_id: &str,
+    path: &Path,
+    method: models::DeleteMethod,
+    quarantine_directory: Option<&Path>,
+    search_root: &Path,
+) -> Result<()> {
+    // The digest is taken before the move so a modified file can be detected on restore
+    let digest_before = image_processing::compute_digest(&path.to_string_lossy())?;
+    let journal_entry = |destination: Option<String>, digest_after: Option<String>| {
+        models::action_journal::ActionJournalInput {
+            batch_id: batch_id.to_string(),
+            operation: method.into(),
+            source: path.to_string_lossy().to_string(),
+            destination,
+            digest_before: Some(digest_before.clone()),
+            digest_after,
+            reverts_batch_id: None,
+            reverts_entry_id: None,
+        }
+    };
+
+    // A removed file can not be brought back, so the entry is written first
+    // and only committed once the file is gone
+    if method == models::DeleteMethod::Permanent {
+        return container
+            .action_journal_repository
+            .append_many_with(vec![journal_entry(None, None)], || {
+                file_operations::delete_file(path, method, quarantine_directory, search_root)
+                    .map(|_| ())
+            })
+            .await;
+    }
+
+    let destination =
+        file_operations::delete_file(path, method, quarantine_directory, search_root)?
+            .context("The new location of the file is unknown")?;
+    let journaled = async {
+        let digest_after = image_processing::compute_digest(&destination.to_string_lossy())?;
+        container
+            .action_journal_repository
+            .append_many(vec![journal_entry(
+                Some(destination.to_string_lossy().to_string()),
+                Some(digest_after),
+            )])
+            .await
+            .context("Failed to record the operation in the journal")
+    }
+    .await;
+    // A move which is not in the journal could never be undone, so it is moved back
+    if let Err(e) = journaled {
+        return match move_back(&destination, path, method) {
+            Ok(_) => Err(e.context("The file was moved back")),
+            Err(move_back_error) => Err(e.context(format!(
+                "Failed to move the file back from {}: {move_back_error:#}",
+                destination.display()
+            ))),
+        };
+    }
+
+    if method == models::DeleteMethod::Quarantine {
+        container
+            .quarantine_entry_repository
+            .create(models::quarantine_entry::QuarantineEntryInput {
+                original_path: path.to_string_lossy().to_string(),
+                quarantine_path: destination.to_string_lossy().to_string(),
+                digest: digest_before,
+            })
+            .await?;
+    }
+
+    Ok(())
+}
+
+fn move_back(destination: &Path, original_path: &Path, method: models::DeleteMethod) -> Result<()> {
+    match method {
+        models::DeleteMethod::Trash => {
+            file_operations::restore_from_trash(destination, original_path)
+        }
+        models::DeleteMethod::Quarantine => {
+            file_operations::quarantine::restore_from_quarantine(destination, original_path)
+        }
+        models::DeleteMethod::Permanent => anyhow::bail!("The file was deleted permanently"),
+    }
+}
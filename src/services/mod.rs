@@ -0,0 +1,5 @@
+// Operations shared by the server functions and the command line interface
+pub mod deletion;
+pub mod reference_images;
+pub mod search;
+pub mod thumbnails;
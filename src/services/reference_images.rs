@@ -0,0 +1,209 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{bundle, di::Container, image_processing, models};
+
+// Register the files which can be hashed, and return the others with the reason
+// The files are hashed in parallel and `on_hashed` is called from the worker threads
+// as soon as each file is done, then the hashed files are saved in one transaction
+pub async fn register(
+    container: &Container,
+    files: Vec<String>,
+    collection: Option<String>,
+    on_hashed: impl Fn(
+            Result<
+                &models::reference_image::ReferenceImageInput,
+                &models::reference_image::RejectedFile,
+            >,
+        ) + Send
+        + Sync
+        + 'static,
+) -> Result<(
+    models::reference_image::UpsertSummary,
+    Vec<models::reference_image::RejectedFile>,
+)> {
+    let (reference_images, rejected): (Vec<_>, Vec<_>) = tokio::task::spawn_blocking(move || {
+        use rayon::prelude::*;
+        files
+            .par_iter()
+            .map(|file| {
+                let result = reference_image_input(file, collection.clone()).map_err(|e| {
+                    models::reference_image::RejectedFile {
+                        filepath: file.clone(),
+                        reason: format!("{e:#}"),
+                    }
+                });
+                on_hashed(result.as_ref());
+                result
+            })
+            .partition_map(|result| match result {
+                Ok(reference_image) => rayon::iter::Either::Left(reference_image),
+                Err(rejected_file) => rayon::iter::Either::Right(rejected_file),
+            })
+    })
+    .await?;
+
+    let summary = container
+        .reference_image_repository
+        .upsert_many(reference_images)
+        .await?;
+
+    Ok((summary, rejected))
+}
+
+// Hash a single file into the row to register
+pub fn reference_image_input(
+    file: &str,
+    collection: Option<String>,
+) -> Result<models::reference_image::ReferenceImageInput> {
+    if !Path::new(file).is_file() {
+        bail!("Not a file");
+    }
+    let hash = image_processing::compute_hash(file)
+        .context("Not a supported image")?
+        .as_bytes()
+        .to_vec();
+    let digest = image_processing::compute_digest(file).ok();
+    let dimensions = image_processing::image_dimensions(file).ok();
+    Ok(models::reference_image::ReferenceImageInput {
+        filepath: file.to_string(),
+        hash,
+        hash_version: image_processing::HASH_VERSION,
+        digest,
+        width: dimensions.map(|(w, _)| w as i32),
+        height: dimensions.map(|(_, h)| h as i32),
+        collection,
+    })
+}
+
+// Page of the reference image library
+// The existence of the files is only known here, so filtering by it pages through the matching rows in memory
+pub async fn find_page(
+    container: &Container,
+    query: &models::reference_image::ReferenceImageQuery,
+) -> Result<models::reference_image::ReferenceImagePage> {
+    use models::reference_image::{FileStatus, ReferenceImageEntry, ReferenceImagePage};
+
+    // Checking the files touches the disk, so it runs on a blocking thread
+    async fn entries(
+        reference_images: Vec<entity::reference_image::Model>,
+    ) -> Result<Vec<ReferenceImageEntry>> {
+        let entries = tokio::task::spawn_blocking(move || {
+            reference_images
+                .into_iter()
+                .map(|reference_image| ReferenceImageEntry {
+                    is_missing: !Path::new(&reference_image.filepath).exists(),
+                    reference_image,
+                })
+                .collect()
+        })
+        .await?;
+        Ok(entries)
+    }
+
+    let reference_image_repository = &container.reference_image_repository;
+    let page = match query.file_status {
+        FileStatus::Any => {
+            let (reference_images, total) = reference_image_repository.find_page(query).await?;
+            ReferenceImagePage {
+                entries: entries(reference_images).await?,
+                total,
+            }
+        }
+        FileStatus::Present | FileStatus::Missing => {
+            let is_missing = query.file_status == FileStatus::Missing;
+            let entries = entries(reference_image_repository.find_all_matching(query).await?)
+                .await?
+                .into_iter()
+                .filter(|e| e.is_missing == is_missing)
+                .collect::<Vec<_>>();
+            ReferenceImagePage {
+                total: entries.len() as u64,
+                entries: entries
+                    .into_iter()
+                    .skip(usize::try_from(query.offset()).unwrap_or(usize::MAX))
+                    .take(query.limit() as usize)
+                    .collect(),
+            }
+        }
+    };
+
+    Ok(page)
+}
+
+// Export the given reference images and every reference image in the given collections
+// into a bundle in the output directory, and return the path of the bundle
+pub async fn export_bundle(
+    container: &Container,
+    reference_image_ids: &[i32],
+    collections: &[String],
+    output_directory: &Path,
+    encoding: bundle::BundleEncoding,
+    include_thumbnails: bool,
+) -> Result<PathBuf> {
+    let reference_image_repository = &container.reference_image_repository;
+    let mut reference_images = reference_image_repository
+        .find_by_ids(reference_image_ids)
+        .await?;
+    reference_images.extend(
+        reference_image_repository
+            .find_by_collections(collections)
+            .await?,
+    );
+    reference_images.sort_by_key(|i| i.id);
+    reference_images.dedup_by_key(|i| i.id);
+
+    if reference_images.is_empty() {
+        bail!("No reference images selected to export");
+    }
+
+    let bundle_path = output_directory.join(format!(
+        "imgdup-references-{}.{}",
+        chrono::Local::now().format("%Y%m%d-%H%M%S"),
+        bundle::BUNDLE_EXTENSION
+    ));
+
+    let result_path = bundle_path.clone();
+    // Encoding the thumbnails decodes every image, so it runs on a blocking thread
+    tokio::task::spawn_blocking(move || {
+        let entries = reference_images
+            .iter()
+            .map(|i| bundle::entry_from_reference_image(i, include_thumbnails))
+            .collect();
+        bundle::write_bundle(&bundle_path, entries, encoding)
+    })
+    .await??;
+
+    Ok(result_path)
+}
+
+// Merge a bundle into the local database
+// Entries already registered with the same content digest are skipped
+// The shipped thumbnails are cached, so images without a local file can still be shown
+pub async fn import_bundle(
+    container: &Container,
+    bundle_path: &Path,
+) -> Result<bundle::ImportReport> {
+    let path = bundle_path.to_path_buf();
+    let (manifest, thumbnails) = tokio::task::spawn_blocking(move || {
+        let manifest = bundle::read_bundle(&path)?;
+        let thumbnails = bundle::read_thumbnails(&path, &manifest.entries)?;
+        anyhow::Ok((manifest, thumbnails))
+    })
+    .await??;
+
+    let reference_image_repository = &container.reference_image_repository;
+    let existing_images = reference_image_repository.find_all().await?;
+
+    let (new_images, report) = bundle::plan_import(manifest, &existing_images);
+    if !new_images.is_empty() {
+        reference_image_repository.create_many(new_images).await?;
+    }
+    container
+        .thumbnail_service
+        .store_imported(thumbnails)
+        .await?;
+
+    Ok(report)
+}
@@ -0,0 +1,239 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use dioxus::logger::tracing::warn;
+
+use crate::{
+    di::Container,
+    file_operations, image_processing,
+    models::{
+        self,
+        settings::{ScanStrategy, Settings},
+    },
+    report::{self, ReportFormat},
+    services::thumbnails::{self, DEFAULT_THUMBNAIL_SIZE},
+};
+
+// Search the directories for images similar to the registered reference images
+// Results are sorted by similarity, most similar first
+pub async fn find_similar_images(
+    container: &Container,
+    directories: &[String],
+    settings: &Settings,
+) -> Result<Vec<models::SimilarImage>> {
+    let reference_images = container.reference_image_repository.find_all().await?;
+    if reference_images.is_empty() {
+        bail!("No reference images registered");
+    }
+
+    // Hashes computed with a different hasher version are not comparable,
+    // so they are left out until they are re-hashed
+    let (reference_images, outdated_images): (Vec<_>, Vec<_>) = reference_images
+        .into_iter()
+        .partition(|i| image_processing::is_current_hash_version(i.hash_version));
+    if !outdated_images.is_empty() {
+        warn!(
+            "Skipping {} reference images hashed with an outdated hasher version",
+            outdated_images.len()
+        );
+    }
+    if reference_images.is_empty() {
+        bail!(
+            "All reference images were hashed with an outdated hasher version. Please re-hash them"
+        );
+    }
+
+    // A malformed hash can not be compared either, so it is skipped the same way
+    let (reference_hashes, reference_filepaths): (Vec<_>, Vec<_>) = reference_images
+        .into_iter()
+        .filter_map(|i| match img_hash::ImageHash::from_bytes(&i.hash) {
+            Ok(hash) => Some((hash, i.filepath)),
+            Err(e) => {
+                warn!(
+                    "Skipping reference image {} with a malformed hash: {e:?}",
+                    i.filepath
+                );
+                None
+            }
+        })
+        .unzip();
+    if reference_hashes.is_empty() {
+        bail!("No reference image has a valid hash. Please re-hash them");
+    }
+
+    let directories = directories.to_vec();
+    let settings = settings.clone();
+    // Scanning, hashing and fingerprinting the files blocks, so it runs on a blocking thread
+    tokio::task::spawn_blocking(move || {
+        compare_images(
+            &directories,
+            &settings,
+            &reference_hashes,
+            &reference_filepaths,
+        )
+    })
+    .await?
+}
+
+fn compare_images(
+    directories: &[String],
+    settings: &Settings,
+    reference_hashes: &[img_hash::ImageHash],
+    reference_filepaths: &[String],
+) -> Result<Vec<models::SimilarImage>> {
+    let mut target_files = Vec::new();
+    for directory in directories {
+        target_files.extend(scan_images(
+            directory,
+            settings.scan_strategy,
+            settings.sample_size,
+        )?);
+    }
+    target_files.sort();
+    target_files.dedup();
+
+    use rayon::prelude::*;
+
+    let mut calc_results = target_files
+        .par_iter()
+        .filter_map(|filepath| {
+            let hash = image_processing::compute_hash(filepath).ok()?;
+            let (max_similarity, matched_reference) = reference_hashes
+                .iter()
+                .enumerate()
+                .map(|(i, ref_hash)| (image_processing::calculate_similarity(ref_hash, &hash), i))
+                .max_by_key(|(similarity, _)| *similarity)
+                .unwrap_or((0, 0));
+            Some((filepath, max_similarity, matched_reference))
+        })
+        .collect::<Vec<_>>();
+
+    calc_results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    let similar_images = calc_results
+        .into_iter()
+        .filter(|(_, s, _)| *s >= settings.similarity_threshold)
+        .take(settings.result_limit)
+        .map(|(file, sim, matched_reference)| {
+            let dimensions = image_processing::image_dimensions(file).ok();
+            models::SimilarImage {
+                filepath: file.to_string(),
+                similarity: sim,
+                matched_reference: Some(reference_filepaths[matched_reference].clone()),
+                width: dimensions.map(|(w, _)| w),
+                height: dimensions.map(|(_, h)| h),
+                is_deleted: false,
+                error_message: None,
+                fingerprint: file_operations::fingerprint(Path::new(file)).ok(),
+            }
+        })
+        .collect();
+
+    Ok(similar_images)
+}
+
+// Collect the image paths from the given directory
+// It will look for the images in the subdirectories as well
+// With `ScanStrategy::Sample` it will only collect the first and last `sample_size` images for each directory
+fn scan_images(
+    directory: &str,
+    scan_strategy: ScanStrategy,
+    sample_size: usize,
+) -> Result<Vec<String>> {
+    let mut targets = Vec::new();
+    for entry in walkdir::WalkDir::new(directory)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_dir())
+    {
+        let mut imgs = std::fs::read_dir(entry.path())
+            .with_context(|| format!("Failed to read directory: {}", entry.path().display()))?
+            .filter_map(Result::ok)
+            .filter(|d| {
+                matches!(
+                    d.path()
+                        .extension()
+                        .unwrap_or_default()
+                        .to_ascii_lowercase()
+                        .to_str()
+                        .unwrap_or_default(),
+                    "jpg" | "jpeg" | "png"
+                )
+            })
+            .map(|d| d.path().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+
+        imgs.sort();
+
+        match scan_strategy {
+            ScanStrategy::Sample if imgs.len() > sample_size * 2 => {
+                let selected = imgs
+                    .iter()
+                    .take(sample_size)
+                    .chain(imgs.iter().rev().take(sample_size))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                targets.extend(selected);
+            }
+            _ => targets.extend(imgs),
+        }
+    }
+
+    Ok(targets)
+}
+
+// Write the search results into the output directory, and return the path of the file
+// Thumbnails are only embedded in the HTML report, and images which are gone are shown without one
+pub async fn export_results(
+    container: &Container,
+    report: &models::SearchReport,
+    format: ReportFormat,
+    output_directory: &Path,
+) -> Result<PathBuf> {
+    let mut thumbnails = HashMap::new();
+    if format == ReportFormat::Html {
+        // The report comes from the client, so only the images the application shows are embedded
+        let paths = report
+            .similar_images
+            .iter()
+            .flat_map(|i| std::iter::once(&i.filepath).chain(i.matched_reference.as_ref()))
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        for path in paths {
+            match thumbnails::is_allowed(container, Path::new(&path)).await {
+                Ok(true) => {}
+                Ok(false) => {
+                    warn!("Skipped the thumbnail of {path}: not a searched or registered image");
+                    continue;
+                }
+                Err(e) => {
+                    warn!("Skipped the thumbnail of {path}: {e:#}");
+                    continue;
+                }
+            }
+            match container
+                .thumbnail_service
+                .thumbnail(Path::new(&path), DEFAULT_THUMBNAIL_SIZE)
+                .await
+            {
+                Ok(thumbnail) => {
+                    thumbnails.insert(path, thumbnail);
+                }
+                Err(e) => warn!("Failed to create thumbnail of {path}: {e:#}"),
+            }
+        }
+    }
+
+    let content = report::render(report, format, &thumbnails)?;
+    let report_path = output_directory.join(format!(
+        "imgdup-results-{}.{}",
+        chrono::Local::now().format("%Y%m%d-%H%M%S"),
+        format.extension()
+    ));
+    std::fs::write(&report_path, content)
+        .with_context(|| format!("Failed to write file: {}", report_path.display()))?;
+
+    Ok(report_path)
+}
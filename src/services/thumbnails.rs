@@ -0,0 +1,268 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{bail, Context, Result};
+use dioxus::logger::tracing::warn;
+use futures::StreamExt;
+use sha2::{Digest, Sha256};
+use tokio::sync::Semaphore;
+
+use crate::{di::Container, file_operations, image_processing, models::FileFingerprint};
+
+pub use crate::utils::path::DEFAULT_THUMBNAIL_SIZE;
+
+pub const MAX_THUMBNAIL_SIZE: u32 = 512;
+
+const INDEX_DIRECTORY: &str = "index";
+
+// Generates thumbnails and caches them on disk
+// Thumbnails are stored by the digest of the image content, so copies of an image share them.
+// The digest of every path is indexed with the size and mtime of the file,
+// and is only computed again when one of them changes
+pub struct ThumbnailService {
+    cache_directory: PathBuf,
+    // Decoding large images is expensive, so only a few are processed at the same time
+    semaphore: Arc<Semaphore>,
+}
+
+impl ThumbnailService {
+    pub fn new(cache_directory: PathBuf) -> Self {
+        let concurrency = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(2);
+        Self {
+            cache_directory,
+            semaphore: Arc::new(Semaphore::new(concurrency)),
+        }
+    }
+
+    pub async fn thumbnail(&self, path: &Path, size: u32) -> Result<Vec<u8>> {
+        let _permit = self.semaphore.acquire().await?;
+        let cache_directory = self.cache_directory.clone();
+        let path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || cached_thumbnail(&cache_directory, &path, size)).await?
+    }
+
+    // Differences are shown rarely, so they are not cached
+    pub async fn difference(&self, path: &Path, reference: &Path, size: u32) -> Result<Vec<u8>> {
+        let _permit = self.semaphore.acquire().await?;
+        let path = path.to_string_lossy().to_string();
+        let reference = reference.to_string_lossy().to_string();
+        tokio::task::spawn_blocking(move || {
+            image_processing::encode_difference(&path, &reference, size)
+        })
+        .await?
+    }
+
+    // Thumbnails shipped in a bundle, for reference images whose file is not on this machine
+    pub async fn store_imported(&self, thumbnails: Vec<(String, Vec<u8>)>) -> Result<()> {
+        let cache_directory = self.cache_directory.clone();
+        tokio::task::spawn_blocking(move || {
+            for (digest, thumbnail) in thumbnails {
+                let result = imported_thumbnail_path(&cache_directory, &digest)
+                    .and_then(|path| write_cache(&path, &thumbnail));
+                if let Err(e) = result {
+                    warn!("Failed to cache imported thumbnail: {e:#}");
+                }
+            }
+        })
+        .await?;
+        Ok(())
+    }
+
+    pub async fn imported(&self, digest: &str) -> Result<Vec<u8>> {
+        let path = imported_thumbnail_path(&self.cache_directory, digest)?;
+        tokio::fs::read(&path)
+            .await
+            .with_context(|| format!("No imported thumbnail: {}", path.display()))
+    }
+
+    // Generate the thumbnails in the background, so they are cached when the UI requests them
+    pub fn prefetch(self: &Arc<Self>, paths: Vec<String>, size: u32) {
+        let service = self.clone();
+        tokio::spawn(async move {
+            futures::stream::iter(paths)
+                .for_each_concurrent(None, |path| {
+                    let service = service.clone();
+                    async move {
+                        if let Err(e) = service.thumbnail(Path::new(&path), size).await {
+                            warn!("Failed to create thumbnail of {path}: {e:#}");
+                        }
+                    }
+                })
+                .await;
+        });
+    }
+}
+
+// Thumbnails are only served for images the application shows:
+// files under the directory of the last search, reference images and quarantined images
+pub async fn is_allowed(container: &Container, path: &Path) -> Result<bool> {
+    let canonical_path = path
+        .canonicalize()
+        .with_context(|| format!("File not found: {}", path.display()))?;
+    if let Some(session) = container.search_session.read().await.as_ref() {
+        if canonical_path.starts_with(session.root()) {
+            return Ok(true);
+        }
+    }
+
+    let filepaths = vec![
+        path.to_string_lossy().to_string(),
+        canonical_path.to_string_lossy().to_string(),
+    ];
+    if !container
+        .reference_image_repository
+        .find_by_filepaths(&filepaths)
+        .await?
+        .is_empty()
+    {
+        return Ok(true);
+    }
+    for filepath in &filepaths {
+        if container
+            .quarantine_entry_repository
+            .find_by_quarantine_path(filepath)
+            .await?
+            .is_some()
+        {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+fn cached_thumbnail(cache_directory: &Path, path: &Path, size: u32) -> Result<Vec<u8>> {
+    let digest = source_digest(cache_directory, path)?;
+    let thumbnail_path = cache_directory
+        .join(&digest[..2])
+        .join(format!("{digest}-{size}.jpg"));
+    if let Ok(thumbnail) = std::fs::read(&thumbnail_path) {
+        return Ok(thumbnail);
+    }
+
+    let thumbnail = image_processing::encode_thumbnail(&path.to_string_lossy(), size)?;
+    // The thumbnail is still served when it can not be cached
+    if let Err(e) = write_cache(&thumbnail_path, &thumbnail) {
+        warn!(
+            "Failed to cache thumbnail {}: {e:#}",
+            thumbnail_path.display()
+        );
+    }
+    Ok(thumbnail)
+}
+
+// The digest comes from a bundle, so it is checked before it becomes part of a path
+fn imported_thumbnail_path(cache_directory: &Path, digest: &str) -> Result<PathBuf> {
+    if digest.len() != 64 || !digest.bytes().all(|b| b.is_ascii_hexdigit()) {
+        bail!("Invalid digest: {digest}");
+    }
+    Ok(cache_directory
+        .join(&digest[..2])
+        .join(format!("{digest}-imported.jpg")))
+}
+
+// Digest of the file content, taken from the index while the size and mtime of the file match
+fn source_digest(cache_directory: &Path, path: &Path) -> Result<String> {
+    let metadata =
+        std::fs::metadata(path).with_context(|| format!("File not found: {}", path.display()))?;
+    let index_path = cache_directory.join(INDEX_DIRECTORY).join(format!(
+        "{:x}.json",
+        Sha256::digest(path.to_string_lossy().as_bytes())
+    ));
+
+    let indexed = std::fs::read(&index_path)
+        .ok()
+        .and_then(|content| serde_json::from_slice::<FileFingerprint>(&content).ok());
+    if let Some(indexed) = indexed {
+        if indexed.size == metadata.len()
+            && indexed.modified_at == metadata.modified().ok().map(Into::into)
+        {
+            return Ok(indexed.digest);
+        }
+    }
+
+    let fingerprint = file_operations::fingerprint(path)?;
+    let result = serde_json::to_vec(&fingerprint)
+        .context("Failed to encode thumbnail index")
+        .and_then(|content| write_cache(&index_path, &content));
+    if let Err(e) = result {
+        warn!("Failed to index {}: {e:#}", path.display());
+    }
+    Ok(fingerprint.digest)
+}
+
+// Write through a temporary file, so concurrent readers never see a partial file
+fn write_cache(path: &Path, content: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+    let temporary_path = path.with_extension(format!("tmp.{}", std::process::id()));
+    std::fs::write(&temporary_path, content)
+        .with_context(|| format!("Failed to write file: {}", temporary_path.display()))?;
+    std::fs::rename(&temporary_path, path)
+        .with_context(|| format!("Failed to write file: {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_digest() {
+        let dir = std::env::temp_dir().join(format!("imgdup-thumbnails-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache_directory = dir.join("cache");
+        let path = dir.join("image.png");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&path, b"first").unwrap();
+
+        let digest = source_digest(&cache_directory, &path).unwrap();
+        assert_eq!(
+            digest,
+            image_processing::compute_digest(&path.to_string_lossy()).unwrap()
+        );
+
+        // The indexed digest is used while the file is unchanged
+        let index_path = std::fs::read_dir(cache_directory.join(INDEX_DIRECTORY))
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        let mut indexed: FileFingerprint =
+            serde_json::from_slice(&std::fs::read(&index_path).unwrap()).unwrap();
+        indexed.digest = "indexed".to_string();
+        std::fs::write(&index_path, serde_json::to_vec(&indexed).unwrap()).unwrap();
+        assert_eq!(source_digest(&cache_directory, &path).unwrap(), "indexed");
+
+        // A different size invalidates the index entry
+        std::fs::write(&path, b"second image").unwrap();
+        assert_eq!(
+            source_digest(&cache_directory, &path).unwrap(),
+            image_processing::compute_digest(&path.to_string_lossy()).unwrap()
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_imported_thumbnail_path() {
+        let cache_directory = Path::new("/cache");
+        let digest = "ab".repeat(32);
+        assert_eq!(
+            imported_thumbnail_path(cache_directory, &digest).unwrap(),
+            cache_directory
+                .join("ab")
+                .join(format!("{digest}-imported.jpg"))
+        );
+        assert!(imported_thumbnail_path(cache_directory, "../../etc/passwd").is_err());
+        assert!(
+            imported_thumbnail_path(cache_directory, &format!("../{}", "a".repeat(61))).is_err()
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use anyhow::{Context, Result};
+
+use crate::models::settings::Settings;
+
+const APP_DIRECTORY: &str = "imgdup";
+const CONFIG_FILE: &str = "config.toml";
+const DATABASE_FILE: &str = "db.sqlite";
+const THUMBNAIL_DIRECTORY: &str = "thumbnails";
+
+// Values given through environment variables and command line options
+// They take precedence over the config file and are never persisted
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Overrides {
+    pub config_path: Option<PathBuf>,
+    pub database_path: Option<PathBuf>,
+    pub similarity_threshold: Option<u32>,
+    pub result_limit: Option<usize>,
+}
+
+impl Overrides {
+    pub fn from_env() -> Result<Self> {
+        let env_var = |key: &str| std::env::var(key).ok().filter(|v| !v.is_empty());
+        Ok(Self {
+            config_path: env_var("IMGDUP_CONFIG").map(PathBuf::from),
+            database_path: env_var("IMGDUP_DATABASE").map(PathBuf::from),
+            similarity_threshold: env_var("IMGDUP_SIMILARITY_THRESHOLD")
+                .map(|v| v.parse())
+                .transpose()
+                .context("Invalid IMGDUP_SIMILARITY_THRESHOLD")?,
+            result_limit: env_var("IMGDUP_RESULT_LIMIT")
+                .map(|v| v.parse())
+                .transpose()
+                .context("Invalid IMGDUP_RESULT_LIMIT")?,
+        })
+    }
+}
+
+static OVERRIDES: OnceLock<Overrides> = OnceLock::new();
+
+// Must be called before the settings are loaded for the first time
+pub fn init(overrides: Overrides) {
+    let _ = OVERRIDES.set(overrides);
+}
+
+fn overrides() -> &'static Overrides {
+    OVERRIDES.get_or_init(Overrides::default)
+}
+
+pub fn config_path() -> Result<PathBuf> {
+    if let Some(config_path) = &overrides().config_path {
+        return Ok(config_path.clone());
+    }
+    let config_directory = dirs::config_dir().context("Failed to find the config directory")?;
+    Ok(config_directory.join(APP_DIRECTORY).join(CONFIG_FILE))
+}
+
+pub fn database_path(settings: &Settings) -> Result<PathBuf> {
+    if let Some(database_path) = &overrides().database_path {
+        return Ok(database_path.clone());
+    }
+    if let Some(database_path) = &settings.database_path {
+        return Ok(PathBuf::from(database_path));
+    }
+    let data_directory = dirs::data_dir().context("Failed to find the data directory")?;
+    Ok(data_directory.join(APP_DIRECTORY).join(DATABASE_FILE))
+}
+
+// Earlier versions created the database in the working directory
+// Returns that file when it still exists and is not the database in use
+pub fn unused_legacy_database(database_path: &Path) -> Option<PathBuf> {
+    unused_database(Path::new(DATABASE_FILE), database_path)
+}
+
+fn unused_database(legacy_path: &Path, database_path: &Path) -> Option<PathBuf> {
+    let legacy_path = legacy_path.canonicalize().ok()?;
+    if database_path
+        .canonicalize()
+        .is_ok_and(|path| path == legacy_path)
+    {
+        return None;
+    }
+    Some(legacy_path)
+}
+
+pub fn thumbnail_cache_directory() -> Result<PathBuf> {
+    let cache_directory = dirs::cache_dir().context("Failed to find the cache directory")?;
+    Ok(cache_directory
+        .join(APP_DIRECTORY)
+        .join(THUMBNAIL_DIRECTORY))
+}
+
+// Settings in effect, with the overrides applied
+pub fn load() -> Result<Settings> {
+    let mut settings = load_persisted()?;
+    let overrides = overrides();
+    if let Some(similarity_threshold) = overrides.similarity_threshold {
+        settings.similarity_threshold = similarity_threshold;
+    }
+    if let Some(result_limit) = overrides.result_limit {
+        settings.result_limit = result_limit;
+    }
+    Ok(settings)
+}
+
+// Settings as stored in the config file
+pub fn load_persisted() -> Result<Settings> {
+    load_from(&config_path()?)
+}
+
+pub fn save(settings: &Settings) -> Result<()> {
+    save_to(&config_path()?, settings)
+}
+
+fn load_from(path: &Path) -> Result<Settings> {
+    if !path.exists() {
+        return Ok(Settings::default());
+    }
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read settings: {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("Invalid settings: {}", path.display()))
+}
+
+fn save_to(path: &Path, settings: &Settings) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+    let content = toml::to_string_pretty(settings).context("Failed to encode settings")?;
+    std::fs::write(path, content)
+        .with_context(|| format!("Failed to write settings: {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{settings::ScanStrategy, DeleteMethod};
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("imgdup-settings-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("config.toml");
+
+        assert_eq!(load_from(&path).unwrap(), Settings::default());
+
+        let settings = Settings {
+            similarity_threshold: 95,
+            scan_strategy: ScanStrategy::All,
+            delete_method: DeleteMethod::Quarantine,
+            quarantine_directory: Some("/tmp/quarantine".to_string()),
+            ..Default::default()
+        };
+        save_to(&path, &settings).unwrap();
+        assert_eq!(load_from(&path).unwrap(), settings);
+
+        // Missing keys fall back to the defaults
+        std::fs::write(&path, "result_limit = 50\n").unwrap();
+        assert_eq!(
+            load_from(&path).unwrap(),
+            Settings {
+                result_limit: 50,
+                ..Default::default()
+            }
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_unused_database() {
+        let dir = std::env::temp_dir().join(format!("imgdup-legacy-db-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("data")).unwrap();
+        let legacy_path = dir.join("db.sqlite");
+        let database_path = dir.join("data/db.sqlite");
+
+        assert_eq!(unused_database(&legacy_path, &database_path), None);
+
+        std::fs::write(&legacy_path, "").unwrap();
+        assert_eq!(
+            unused_database(&legacy_path, &database_path),
+            Some(legacy_path.canonicalize().unwrap())
+        );
+        // The legacy file is the database in use when it is configured explicitly
+        assert_eq!(
+            unused_database(&legacy_path, &dir.join("data/../db.sqlite")),
+            None
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
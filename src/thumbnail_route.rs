@@ -0,0 +1,167 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use anyhow::Context;
+use axum::{
+    extract::Query,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use dioxus::logger::tracing::warn;
+use serde::Deserialize;
+
+use crate::{di::get_container, services::thumbnails, utils};
+
+// Browsers can not load server-local paths, so the UI loads images through this route
+// Every request needs the token, which is created at startup and handed to the UI
+// through `backend::get_thumbnail_token`, and only the images the application shows
+// are served, see `thumbnails::is_allowed`
+static TOKEN: OnceLock<String> = OnceLock::new();
+
+pub fn token() -> &'static str {
+    TOKEN.get_or_init(|| {
+        // Every RandomState is seeded with random keys
+        (0..4)
+            .map(|_| format!("{:016x}", RandomState::new().build_hasher().finish()))
+            .collect()
+    })
+}
+
+pub fn router() -> Router {
+    Router::new()
+        .route(utils::path::THUMBNAIL_ROUTE, get(thumbnail))
+        .route(utils::path::DIFFERENCE_ROUTE, get(difference))
+}
+
+#[derive(Debug, Deserialize)]
+struct ThumbnailQuery {
+    path: String,
+    token: Option<String>,
+    size: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DifferenceQuery {
+    path: String,
+    reference: String,
+    token: Option<String>,
+    size: Option<u32>,
+}
+
+// Only JPEG is served, the image crate can not encode WebP
+async fn thumbnail(Query(query): Query<ThumbnailQuery>) -> Response {
+    let path = PathBuf::from(&query.path);
+    // Reference images imported from a bundle may have no file here, only the shipped thumbnail
+    if !path.exists() {
+        if !is_valid_token(query.token.as_deref()) {
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+        let result = imported_thumbnail(&query.path).await;
+        return image_response(result, "image/jpeg", &query.path);
+    }
+    if let Err(response) = authorize(query.token.as_deref(), &[&path]).await {
+        return response;
+    }
+
+    let result = get_container()
+        .await
+        .thumbnail_service
+        .thumbnail(&path, thumbnail_size(query.size))
+        .await;
+    image_response(result, "image/jpeg", &query.path)
+}
+
+async fn difference(Query(query): Query<DifferenceQuery>) -> Response {
+    let path = PathBuf::from(&query.path);
+    let reference = PathBuf::from(&query.reference);
+    if let Err(response) = authorize(query.token.as_deref(), &[&path, &reference]).await {
+        return response;
+    }
+
+    let result = get_container()
+        .await
+        .thumbnail_service
+        .difference(&path, &reference, thumbnail_size(query.size))
+        .await;
+    image_response(result, "image/png", &query.path)
+}
+
+async fn imported_thumbnail(filepath: &str) -> anyhow::Result<Vec<u8>> {
+    let container = get_container().await;
+    let digest = container
+        .reference_image_repository
+        .find_by_filepaths(&vec![filepath.to_string()])
+        .await?
+        .into_iter()
+        .find_map(|reference_image| reference_image.digest)
+        .with_context(|| format!("File not found: {filepath}"))?;
+    container.thumbnail_service.imported(&digest).await
+}
+
+fn is_valid_token(token: Option<&str>) -> bool {
+    token == Some(self::token())
+}
+
+// Check the token and that every image may be served
+async fn authorize(token: Option<&str>, paths: &[&Path]) -> Result<(), Response> {
+    if !is_valid_token(token) {
+        return Err(StatusCode::UNAUTHORIZED.into_response());
+    }
+
+    let container = get_container().await;
+    for path in paths {
+        match thumbnails::is_allowed(container, path).await {
+            Ok(true) => {}
+            Ok(false) => return Err(StatusCode::FORBIDDEN.into_response()),
+            Err(e) => {
+                warn!("Failed to check thumbnail path {}: {e:#}", path.display());
+                return Err(StatusCode::NOT_FOUND.into_response());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn thumbnail_size(size: Option<u32>) -> u32 {
+    size.unwrap_or(thumbnails::DEFAULT_THUMBNAIL_SIZE)
+        .clamp(1, thumbnails::MAX_THUMBNAIL_SIZE)
+}
+
+fn image_response(result: anyhow::Result<Vec<u8>>, content_type: &str, path: &str) -> Response {
+    match result {
+        Ok(image) => (
+            [
+                (header::CONTENT_TYPE, content_type),
+                (header::CACHE_CONTROL, "private, max-age=300"),
+            ],
+            image,
+        )
+            .into_response(),
+        Err(e) => {
+            warn!("Failed to create thumbnail of {path}: {e:#}");
+            StatusCode::NOT_FOUND.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_authorize_rejects_missing_or_wrong_token() {
+        let path = Path::new("/images/test.png");
+
+        let response = authorize(None, &[path]).await.unwrap_err();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = authorize(Some("wrong"), &[path]).await.unwrap_err();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -1,14 +1,47 @@
-// https://github.com/DioxusLabs/dioxus/issues/1814
-// https://github.com/tauri-apps/tauri/blob/f37e97d410c4a219e99f97692da05ca9d8e0ba3a/crates/tauri/scripts/core.js#L17
-pub fn normalize_path(p: &str) -> String {
-    #[cfg(target_os = "windows")]
-    {
-        let p = p.replace("\\", "/");
-        let p = p.replace("//", "/");
-        format!("http://dioxus.localhost/{p}")
-    }
-    #[cfg(target_os = "macos")]
-    {
-        format!("{p}")
+pub const THUMBNAIL_ROUTE: &str = "/thumbnails";
+pub const DIFFERENCE_ROUTE: &str = "/thumbnails/difference";
+pub const DEFAULT_THUMBNAIL_SIZE: u32 = 128;
+
+// URL of the thumbnail of a server-local image, see `thumbnail_route`
+pub fn thumbnail_url(p: &str, token: &str, size: u32) -> String {
+    format!(
+        "{}{THUMBNAIL_ROUTE}?path={}&token={token}&size={size}",
+        dioxus::prelude::server_fn::client::get_server_url(),
+        encode_query_value(p)
+    )
+}
+
+// URL of the difference image of two server-local images
+pub fn difference_url(p: &str, reference: &str, token: &str, size: u32) -> String {
+    format!(
+        "{}{DIFFERENCE_ROUTE}?path={}&reference={}&token={token}&size={size}",
+        dioxus::prelude::server_fn::client::get_server_url(),
+        encode_query_value(p),
+        encode_query_value(reference)
+    )
+}
+
+fn encode_query_value(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_query_value() {
+        assert_eq!(
+            encode_query_value("/home/me/a b&c=ü.png"),
+            "%2Fhome%2Fme%2Fa%20b%26c%3D%C3%BC.png"
+        );
     }
 }